@@ -0,0 +1,53 @@
+//! Compares how fast the packed and grid board representations generate and play moves.
+//!
+//! Run with `cargo bench --bench move_generation`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use color_wars::prelude::*;
+
+/// Counts the positions reachable in `depth` moves, stopping at finished games.
+fn perft(board: &Board, depth: usize) -> u64 {
+    if depth == 0 || board.is_game_over() {
+        return 1;
+    }
+    board
+        .valid_moves()
+        .map(|placement| perft(&board.make_move(placement), depth - 1))
+        .sum()
+}
+
+/// Runs `perft` from `board` until at least a second has passed, returning the
+/// number of positions visited per second.
+fn nodes_per_second(board: &Board, depth: usize) -> f64 {
+    let start = Instant::now();
+    let mut nodes = 0;
+    while start.elapsed() < Duration::from_secs(1) {
+        nodes += perft(black_box(board), depth);
+    }
+    nodes as f64 / start.elapsed().as_secs_f64()
+}
+
+fn main() {
+    // A position after a few moves has more squares and chain reactions than the setup
+    let dimensions = Dimensions::default();
+    let mut board = Board::setup(
+        dimensions,
+        &[
+            Coordinates::new(1, 1, dimensions),
+            Coordinates::new(3, 3, dimensions),
+        ],
+    );
+    for _ in 0..6 {
+        board = board.make_move(board.get_valid_moves()[0]);
+    }
+
+    let depth = 4;
+    assert_eq!(perft(&board, depth), perft(&board.unpacked(), depth));
+
+    let packed = nodes_per_second(&board, depth);
+    let grid = nodes_per_second(&board.unpacked(), depth);
+    println!("packed: {packed:>12.0} nodes/s");
+    println!("grid:   {grid:>12.0} nodes/s");
+    println!("speedup: {:.2}x", packed / grid);
+}
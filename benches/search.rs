@@ -0,0 +1,40 @@
+//! Reports how many positions the search visits to reach a fixed depth, the measure
+//! of how well it orders moves, and how long it takes.
+//!
+//! Run with `cargo bench --bench search`.
+use color_wars::prelude::*;
+
+fn main() {
+    let dimensions = Dimensions::default();
+    let depth = 12;
+
+    let mut total_nodes = 0;
+    for (first, second, moves) in [
+        ((1, 1), (3, 3), 6),
+        ((0, 0), (4, 4), 10),
+        ((2, 1), (2, 3), 4),
+    ] {
+        let mut board = Board::setup(
+            dimensions,
+            &[
+                Coordinates::new(first.0, first.1, dimensions),
+                Coordinates::new(second.0, second.1, dimensions),
+            ],
+        );
+        // Spread the moves over the board instead of always playing the first one
+        for index in 0..moves {
+            let valid_moves = board.get_valid_moves();
+            board = board.make_move(valid_moves[index % valid_moves.len()]);
+        }
+
+        let result = search(&board, SearchLimits::depth(depth));
+        println!(
+            "depth {depth}: {:>9} nodes in {:>8.1?}, score {}",
+            result.nodes(),
+            result.elapsed(),
+            result.score()
+        );
+        total_nodes += result.nodes();
+    }
+    println!("total: {total_nodes} nodes");
+}
@@ -1,123 +1,227 @@
-use std::time::Duration;
 use std::io::Write;
+use std::time::Duration;
 
 use color_wars::prelude::*;
 
 use colored::{ColoredString, Colorize};
 
 fn main() {
+    // --- Network ---
+    // `--network FILE` plays with a network written by the `train` binary instead of
+    // the standard evaluation. The other arguments are positional.
+    let mut arguments: Vec<String> = std::env::args().skip(1).collect();
+    let network_path = arguments
+        .iter()
+        .position(|argument| argument == "--network")
+        .map(|index| {
+            arguments.remove(index);
+            if index == arguments.len() {
+                eprintln!("Missing network file after --network");
+                std::process::exit(1);
+            }
+            arguments.remove(index)
+        });
+    let argument = |index: usize| arguments.get(index).cloned();
+
+    // --- Board Size ---
+    // The board size can be passed as the first argument, e.g. `8x8`.
+    let dimensions = match argument(0) {
+        Some(argument) => match argument.parse::<Dimensions>() {
+            Ok(dimensions) => dimensions,
+            Err(error) => {
+                eprintln!("{error}");
+                std::process::exit(1);
+            }
+        },
+        None => Dimensions::default(),
+    };
+
+    // --- Player Count ---
+    // The number of players can be passed as the second argument, from 2 to 4.
+    let player_count = match argument(1) {
+        Some(argument) => match argument.parse::<usize>() {
+            Ok(count) if (Player::MIN_PLAYERS..=Player::MAX_PLAYERS).contains(&count) => count,
+            _ => {
+                eprintln!(
+                    "Invalid player count '{argument}', expected {} to {}",
+                    Player::MIN_PLAYERS,
+                    Player::MAX_PLAYERS
+                );
+                std::process::exit(1);
+            }
+        },
+        None => Player::MIN_PLAYERS,
+    };
+
+    // --- Engine ---
+    // The engine can be passed as the third argument, `alphabeta` or `mcts`.
+    let monte_carlo = match argument(2).as_deref() {
+        None | Some("alphabeta") => false,
+        Some("mcts") => true,
+        Some(argument) => {
+            eprintln!("Invalid engine '{argument}', expected alphabeta or mcts");
+            std::process::exit(1);
+        }
+    };
+
+    // --- Evaluation Weights ---
+    // A file of weights written by the `tune` binary can be passed as the fourth
+    // argument, the alpha-beta engine evaluates positions with them.
+    let evaluator = match argument(3) {
+        Some(path) => match EvalParams::load(&path) {
+            Ok(params) => StandardEvaluator::new(params),
+            Err(error) => {
+                eprintln!("Cannot read '{path}': {error}");
+                std::process::exit(1);
+            }
+        },
+        None => StandardEvaluator::default(),
+    };
+
+    let network = network_path.map(|path| match NeuralEvaluator::load(&path) {
+        Ok(network) if network.dimensions() == dimensions => network,
+        Ok(network) => {
+            eprintln!(
+                "The network in '{path}' evaluates {} boards, not {dimensions}",
+                network.dimensions()
+            );
+            std::process::exit(1);
+        }
+        Err(error) => {
+            eprintln!("Cannot read '{path}': {error}");
+            std::process::exit(1);
+        }
+    });
+
     // --- Initial Setup Phase ---
-    // This phase handles the placement of the first two pieces on the board.
-    let board = Board::new(Player::Red);
+    // This phase handles the placement of every player's first piece on the board.
+    let board = Board::new(dimensions, player_count, Player::Red);
     print_board(&board);
 
     // Create a list of all possible coordinates for the initial piece placements.
-    let mut valid_placements = Vec::new();
-    for row in 0..BOARD_ROW_SIZE {
-        for column in 0..BOARD_COLUMN_SIZE {
-            valid_placements.push(Coordinates::new(row, column));
+    let mut valid_placements: Vec<Coordinates> = dimensions.coordinates().collect();
+    let mut placements = Vec::with_capacity(player_count);
+
+    for player in &Player::ALL[..player_count] {
+        // Get the player's starting position.
+        println!("{player} player, choose your starting square.");
+        let placement = input_coordinates(dimensions, &valid_placements);
+
+        // Remove the choice so the following players cannot pick the same square.
+        if let Some(index) = valid_placements
+            .iter()
+            .position(|value| *value == placement)
+        {
+            valid_placements.remove(index);
         }
+        placements.push(placement);
     }
 
-    // Get Player 1 (Red)'s starting position.
-    println!("Red player, choose your starting square.");
-    let red_placement = input_coordinates(&valid_placements);
-
-    // Remove Red's choice so Blue cannot pick the same square.
-    if let Some(index) = valid_placements
-        .iter()
-        .position(|value| *value == red_placement)
-    {
-        valid_placements.remove(index);
-    }
-
-    // Get Player 2 (Blue)'s starting position.
-    println!("Blue player, choose your starting square.");
-    let blue_placement = input_coordinates(&valid_placements);
-
-    // Create the board with the starting pieces.
-    let mut board = Board::setup(red_placement, blue_placement);
-
-    let mut game_history = vec![board.clone()];
+    // Create the game with the starting pieces.
+    let mut game = Game::new(dimensions, &placements);
 
     // --- Main Game Loop ---
-    // The game continues as long as neither player has been eliminated.
-    while !board.is_game_over() {
+    // The game continues as long as more than one player has squares left.
+    // The engine keeps its transposition table from one move to the next, and
+    // searches on every core.
+    let mut transposition_table = TranspositionTable::default();
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    while game.result() == GameResult::Ongoing {
         println!("\x1B[2J\x1B[1;1H");
 
-        print_board(&board);
+        print_board(game.board());
+        println!("Move {}", game.move_number() + 1);
 
         // Use the engine to suggest a move for the current player.
         println!("Engine evaluation:");
-        let (score, best_move) = search(&board, Duration::from_millis(250));
-        println!("Current score: {}", score);
-        println!("Best move: [{},{}]", best_move.row(), best_move.column());
+        let limits = SearchLimits::time(Duration::from_millis(250));
+        let best_move = if monte_carlo {
+            // The Monte Carlo engine searches on a single thread
+            let result = monte_carlo_search(game.board(), limits, &StopHandle::new());
+            println!("Playouts: {}", result.playouts());
+            for statistics in result.moves() {
+                println!(
+                    "{:>4}: {:>6} visits, {:>5.1}% won",
+                    statistics.placement().to_string(),
+                    statistics.visits(),
+                    statistics.win_rate() * 100.0
+                );
+            }
+            result.best_move()
+        } else {
+            let limits = limits.with_threads(threads);
+            let stop = StopHandle::new();
+            let result = match &network {
+                Some(network) => search_with_evaluator(
+                    game.board(),
+                    limits,
+                    &stop,
+                    &mut transposition_table,
+                    network,
+                    |_| {},
+                ),
+                None => search_with_evaluator(
+                    game.board(),
+                    limits,
+                    &stop,
+                    &mut transposition_table,
+                    &evaluator,
+                    |_| {},
+                ),
+            };
+            println!("Searched to depth: {}", result.depth());
+            println!("Current score: {}", result.score());
+            result.best_move()
+        };
+        println!("Best move: {best_move}");
 
         // Get the actual move from the user.
-        // let play = crate::input_coordinates(&board.get_valid_moves());
+        // let play = crate::input_coordinates(dimensions, &game.board().get_valid_moves());
 
         std::thread::sleep(Duration::from_millis(250));
-        board = board.make_move(best_move);
-        game_history.push(board.clone());
+        game.play(best_move)
+            .expect("the engine only suggests valid moves");
     }
 
     // --- Game Over ---
-    // Announce the winner. The winner is the opponent of the player whose turn it is,
-    // because that player has no moves left.
-    print_board(&board);
-    println!("Game over!, {:?} wins", board.turn().opponent());
+    // Announce the result, the winner is the last player with squares on the board.
+    print_board(game.board());
+    println!("Game over!, {}", game.result());
 }
 
-
-
 /// Prompts the user to enter coordinates and loops until a valid move is chosen.
 ///
 /// This function ensures that the user's input corresponds to a coordinate
 /// that is both within the board's bounds and is listed in the `valid_moves` slice.
 ///
 /// # Arguments
+/// * `dimensions` - The size of the board the move is played on.
 /// * `valid_moves` - A slice of `Coordinates` representing all legal moves for the current player.
 ///
 /// # Returns
 /// The `Coordinates` of the valid move selected by the user.
-pub fn input_coordinates(valid_moves: &[Coordinates]) -> Coordinates {
+pub fn input_coordinates(dimensions: Dimensions, valid_moves: &[Coordinates]) -> Coordinates {
     loop {
-        // Prompt for and parse row and column numbers.
-        let row = input_usize("Row: ");
-        let column = input_usize("Column: ");
-
-        // Validate that the coordinates are on the board.
-        if (0..BOARD_ROW_SIZE).contains(&row) && (0..BOARD_COLUMN_SIZE).contains(&column) {
-            let placement = Coordinates::new(row, column);
-
-            // Validate that the chosen square is a legal move.
-            if valid_moves.contains(&placement) {
-                return placement;
-            } else {
-                println!("Invalid move!");
-            }
-        } else {
-            println!("Coordinates not in range!");
-        }
-    }
-}
-
-// A private helper function to reliably get a `usize` from standard input.
-fn input_usize(message: &str) -> usize {
-    loop {
-        print!("{message}");
+        // Prompt for a square in algebraic notation, e.g. `c4`.
+        print!("Square: ");
 
         // Ensure the prompt message is displayed before waiting for input.
         std::io::stdout().flush().unwrap();
 
         let mut value = String::new();
         std::io::stdin().read_line(&mut value).expect("STDIN error");
-        let value = value.trim().parse::<usize>();
 
-        if let Ok(value) = value {
-            return value;
-        } else {
-            println!("Invalid input");
+        // Validate that the coordinates are on the board.
+        match Coordinates::parse(value.trim(), dimensions) {
+            Ok(placement) => {
+                // Validate that the chosen square is a legal move.
+                if valid_moves.contains(&placement) {
+                    return placement;
+                } else {
+                    println!("Invalid move!");
+                }
+            }
+            Err(error) => println!("{error}!"),
         }
     }
 }
@@ -130,26 +234,34 @@ pub fn print_board(board: &Board) {
 }
 
 fn format_board(board: &Board) -> ColoredString {
-    let mut formatted_board = format_column_coordinates_hint();
-    for row_index in 0..BOARD_ROW_SIZE {
-        let formatted_row = format_single_row(row_index, board.grid()[row_index]);
+    let dimensions = board.dimensions();
+    let mut formatted_board = format_column_coordinates_hint(dimensions);
+    for (row_index, row) in board.grid().rows().enumerate() {
+        let formatted_row = format_single_row(row_index, row);
         formatted_board = format!("{formatted_board}{formatted_row}\n").into();
     }
-    formatted_board = format!("{formatted_board}{}", format_column_coordinates_hint()).into();
+    formatted_board = format!(
+        "{formatted_board}{}",
+        format_column_coordinates_hint(dimensions)
+    )
+    .into();
 
-    let formatted_turn = format_turn_bar(board.turn());
+    let formatted_turn = format_turn_bar(board.turn(), dimensions.columns());
 
     formatted_board = format!("{formatted_board}{formatted_turn}").into();
 
     formatted_board
 }
 
-fn format_column_coordinates_hint() -> ColoredString {
+fn format_column_coordinates_hint(dimensions: Dimensions) -> ColoredString {
     let empty_pad = String::from("   ").on_black();
     let mut column_coordinates_hint: ColoredString = empty_pad.clone();
 
-    for column_index in 0..BOARD_COLUMN_SIZE {
-        let formatted_index = format!(" {} ", column_index).white().on_black();
+    for column_index in 0..dimensions.columns() {
+        // The column letters are the algebraic name of the square without its row number
+        let square = Coordinates::new(0, column_index, dimensions).to_string();
+        let letters = square.trim_end_matches(|character: char| character.is_ascii_digit());
+        let formatted_index = format!("{letters:^3}").white().on_black();
         column_coordinates_hint = format!("{column_coordinates_hint}{formatted_index}").into();
     }
 
@@ -158,10 +270,10 @@ fn format_column_coordinates_hint() -> ColoredString {
     column_coordinates_hint
 }
 
-fn format_turn_bar(player: Player) -> ColoredString {
+fn format_turn_bar(player: Player, columns: usize) -> ColoredString {
     let turn = format_turn(player);
     let mut turn_bar = String::new().into();
-    for _ in 0..(BOARD_COLUMN_SIZE + 2) {
+    for _ in 0..(columns + 2) {
         turn_bar = format!("{turn_bar}{}", turn.clone()).into();
     }
 
@@ -174,18 +286,20 @@ fn format_turn(player: Player) -> ColoredString {
     match player {
         Player::Red => turn.on_red(),
         Player::Blue => turn.on_blue(),
+        Player::Green => turn.on_green(),
+        Player::Yellow => turn.on_yellow(),
     }
 }
 
 // Formats a single row of the board, including side coordinate hints.
-fn format_single_row(i: usize, row: [Square; BOARD_COLUMN_SIZE]) -> ColoredString {
+fn format_single_row(i: usize, row: &[Square]) -> ColoredString {
     let mut formatted_row: ColoredString = String::new().into();
 
-    let row_coordinates_hint = format!(" {i} ").white().on_black();
+    let row_coordinates_hint = format!("{:>2} ", i + 1).white().on_black();
     formatted_row = format!("{formatted_row}{row_coordinates_hint}").into();
 
     for square in row {
-        formatted_row = format!("{formatted_row}{}", format_single_square(square)).into();
+        formatted_row = format!("{formatted_row}{}", format_single_square(*square)).into();
     }
     formatted_row = format!("{formatted_row}{row_coordinates_hint}").into();
 
@@ -199,6 +313,8 @@ fn format_single_square(square: Square) -> ColoredString {
         match player {
             Player::Red => value.on_red(),
             Player::Blue => value.on_blue(),
+            Player::Green => value.on_green(),
+            Player::Yellow => value.on_yellow(),
         }
     } else {
         String::from(" ▪ ").white().on_black()
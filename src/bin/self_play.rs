@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+
+use color_wars::prelude::*;
+
+/// The depth the engine searches every move to.
+const SEARCH_DEPTH: usize = 3;
+
+/// The number of games played when none is given.
+const DEFAULT_GAMES: usize = 100;
+
+fn main() {
+    // --- Output Directory ---
+    // Every game is written as a record to the directory given as first argument.
+    let Some(directory) = std::env::args().nth(1).map(PathBuf::from) else {
+        eprintln!("Usage: self_play DIRECTORY [GAMES] [ROWSxCOLUMNS] [NETWORK] [SEED]");
+        std::process::exit(1);
+    };
+    if let Err(error) = std::fs::create_dir_all(&directory) {
+        eprintln!("Cannot create '{}': {error}", directory.display());
+        std::process::exit(1);
+    }
+
+    // --- Number of Games ---
+    let games = match std::env::args().nth(2) {
+        Some(argument) => match argument.parse::<usize>() {
+            Ok(games) if games > 0 => games,
+            _ => {
+                eprintln!("Invalid number of games '{argument}'");
+                std::process::exit(1);
+            }
+        },
+        None => DEFAULT_GAMES,
+    };
+
+    // --- Board Size ---
+    let dimensions = match std::env::args().nth(3) {
+        Some(argument) => match argument.parse::<Dimensions>() {
+            Ok(dimensions) => dimensions,
+            Err(error) => {
+                eprintln!("{error}");
+                std::process::exit(1);
+            }
+        },
+        None => Dimensions::default(),
+    };
+
+    // --- Evaluator ---
+    // A network written by the `train` binary can be passed as the fourth argument,
+    // the engine then plays with it instead of the standard evaluation.
+    let network = std::env::args()
+        .nth(4)
+        .map(|path| match NeuralEvaluator::load(&path) {
+            Ok(network) if network.dimensions() == dimensions => network,
+            Ok(network) => {
+                eprintln!(
+                    "The network in '{path}' evaluates {} boards, not {dimensions}",
+                    network.dimensions()
+                );
+                std::process::exit(1);
+            }
+            Err(error) => {
+                eprintln!("Cannot read '{path}': {error}");
+                std::process::exit(1);
+            }
+        });
+
+    // --- Seed ---
+    // The games are drawn from the fifth argument, or from the clock so every run
+    // plays new games. The seed is written to the records to replay them.
+    let seed = match std::env::args().nth(5) {
+        Some(argument) => match argument.parse::<u64>() {
+            Ok(seed) => seed,
+            Err(_) => {
+                eprintln!("Invalid seed '{argument}'");
+                std::process::exit(1);
+            }
+        },
+        None => std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64),
+    };
+
+    // --- Self-Play ---
+    // Games are numbered after the highest numbered record already in the directory,
+    // so no record is overwritten even when earlier ones were removed.
+    let first = std::fs::read_dir(&directory).map_or(0, |entries| {
+        entries
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name().into_string().ok()?;
+                let number = name.strip_prefix("game_")?.strip_suffix(".txt")?;
+                number.parse::<usize>().ok()
+            })
+            .max()
+            .map_or(0, |highest| highest + 1)
+    });
+    let limits = SearchLimits::depth(SEARCH_DEPTH);
+    let played = match &network {
+        Some(network) => self_play_games(
+            dimensions,
+            Player::MIN_PLAYERS,
+            games,
+            limits,
+            network,
+            seed,
+        ),
+        None => self_play_games(
+            dimensions,
+            Player::MIN_PLAYERS,
+            games,
+            limits,
+            &StandardEvaluator::default(),
+            seed,
+        ),
+    };
+
+    for (index, game) in played.iter().enumerate() {
+        let mut record = GameRecord::new(game);
+        let engine = if network.is_some() {
+            "network"
+        } else {
+            "standard"
+        };
+        record.set_engine_settings(&format!(
+            "depth {SEARCH_DEPTH}, {engine} evaluation, seed {seed} game {index}"
+        ));
+        let path = directory.join(format!("game_{:05}.txt", first + index));
+        if let Err(error) = std::fs::write(&path, record.to_string()) {
+            eprintln!("Cannot write '{}': {error}", path.display());
+            std::process::exit(1);
+        }
+        println!("{}: {}", path.display(), game.result());
+    }
+}
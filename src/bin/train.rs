@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+use color_wars::prelude::*;
+
+/// The number of hidden neurons of a new network.
+const HIDDEN_NEURONS: usize = 32;
+
+/// The number of passes over the positions.
+const EPOCHS: usize = 20;
+
+/// The size of the gradient descent steps.
+const LEARNING_RATE: f32 = 0.01;
+
+fn main() {
+    // --- Arguments ---
+    // The network is written to the first argument, and trained on the game records
+    // given after it, files or directories of files. When the network file already
+    // exists, training continues from the weights it holds.
+    let mut arguments = std::env::args().skip(1).map(PathBuf::from);
+    let (Some(output), records) = (arguments.next(), arguments.collect::<Vec<_>>()) else {
+        eprintln!("Usage: train NETWORK RECORD...");
+        std::process::exit(1);
+    };
+    if records.is_empty() {
+        eprintln!("Usage: train NETWORK RECORD...");
+        std::process::exit(1);
+    }
+
+    // --- Training Positions ---
+    let mut games = Vec::new();
+    for path in records.iter().flat_map(|path| record_files(path)) {
+        let game = std::fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|text| {
+                text.parse::<GameRecord>()
+                    .map_err(|error| error.to_string())
+            })
+            .and_then(|record| record.replay().map_err(|error| error.to_string()));
+        match game {
+            Ok(game) => games.push(game),
+            Err(error) => eprintln!("Skipping '{}': {error}", path.display()),
+        }
+    }
+    let Some(first_game) = games.first() else {
+        eprintln!("No game records found");
+        std::process::exit(1);
+    };
+
+    let mut network = if output.exists() {
+        match NeuralEvaluator::load(&output) {
+            Ok(network) => network,
+            Err(error) => {
+                eprintln!("Cannot read '{}': {error}", output.display());
+                std::process::exit(1);
+            }
+        }
+    } else {
+        NeuralEvaluator::new(first_game.dimensions(), HIDDEN_NEURONS, 0)
+    };
+    let positions: Vec<TrainingPosition> = games
+        .iter()
+        .filter(|game| game.dimensions() == network.dimensions())
+        .flat_map(training_positions)
+        .collect();
+    println!(
+        "Training a {} network on {} positions from {} games",
+        network.dimensions(),
+        positions.len(),
+        games.len()
+    );
+
+    // --- Training ---
+    let loss = network.train(&positions, EPOCHS, LEARNING_RATE);
+    println!("Loss after {EPOCHS} epochs: {loss:.4}");
+    if let Err(error) = network.save(&output) {
+        eprintln!("Cannot write '{}': {error}", output.display());
+        std::process::exit(1);
+    }
+    println!("Saved to {}", output.display());
+}
+
+/// Returns `path` if it is a file, or the files in it, sorted, if it is a directory.
+fn record_files(path: &Path) -> Vec<PathBuf> {
+    if !path.is_dir() {
+        return vec![path.to_path_buf()];
+    }
+    let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_file())
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use color_wars::prelude::*;
+
+/// The depth the engine searches every move of the self-play games to.
+const SEARCH_DEPTH: usize = 3;
+
+/// The number of games played when none is given.
+const DEFAULT_GAMES: usize = 100;
+
+fn main() {
+    // --- Output File ---
+    // The tuned weights are written to the first argument. When the file already
+    // exists, tuning starts from the weights it holds.
+    let Some(output) = std::env::args().nth(1).map(PathBuf::from) else {
+        eprintln!("Usage: tune OUTPUT [GAMES] [ROWSxCOLUMNS] [SEED]");
+        std::process::exit(1);
+    };
+    let params = if output.exists() {
+        match EvalParams::load(&output) {
+            Ok(params) => params,
+            Err(error) => {
+                eprintln!("Cannot read '{}': {error}", output.display());
+                std::process::exit(1);
+            }
+        }
+    } else {
+        EvalParams::default()
+    };
+
+    // --- Number of Games ---
+    let games = match std::env::args().nth(2) {
+        Some(argument) => match argument.parse::<usize>() {
+            Ok(games) if games > 0 => games,
+            _ => {
+                eprintln!("Invalid number of games '{argument}'");
+                std::process::exit(1);
+            }
+        },
+        None => DEFAULT_GAMES,
+    };
+
+    // --- Board Size ---
+    let dimensions = match std::env::args().nth(3) {
+        Some(argument) => match argument.parse::<Dimensions>() {
+            Ok(dimensions) => dimensions,
+            Err(error) => {
+                eprintln!("{error}");
+                std::process::exit(1);
+            }
+        },
+        None => Dimensions::default(),
+    };
+
+    // --- Seed ---
+    // The games are drawn from the fourth argument, or from the clock so every run
+    // tunes on new games. The seed is printed to replay a run.
+    let seed = match std::env::args().nth(4) {
+        Some(argument) => match argument.parse::<u64>() {
+            Ok(seed) => seed,
+            Err(_) => {
+                eprintln!("Invalid seed '{argument}'");
+                std::process::exit(1);
+            }
+        },
+        None => std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64),
+    };
+
+    // --- Self-Play ---
+    println!("Playing {games} games on a {dimensions} board from seed {seed} with:\n{params}");
+    let positions = self_play(
+        dimensions,
+        Player::MIN_PLAYERS,
+        games,
+        SearchLimits::depth(SEARCH_DEPTH),
+        &params,
+        seed,
+    );
+    println!("Recorded {} positions", positions.len());
+
+    // --- Tuning ---
+    let tuned = tune(&positions, params);
+    println!("Tuned weights:\n{tuned}");
+    if let Err(error) = tuned.save(&output) {
+        eprintln!("Cannot write '{}': {error}", output.display());
+        std::process::exit(1);
+    }
+    println!("Saved to {}", output.display());
+}
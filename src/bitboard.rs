@@ -0,0 +1,279 @@
+//! Defines `Bitboards`, a packed copy of the grid that answers the engine's hot
+//! questions with bit operations instead of scanning every square.
+use crate::{coordinates::Coordinates, dimensions::Dimensions, player::Player, square::Square};
+
+/// One bit per square, in row-major order.
+pub(crate) type Mask = u128;
+
+/// The squares of a board packed into bitmasks, one per player and one per bit of
+/// the square values.
+///
+/// Values are stored bit-sliced, so a square's value is the sum of the planes it is
+/// set in. Values of `4` and more only exist while a move is being resolved, they
+/// are the squares about to pop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Bitboards {
+    dimensions: Dimensions,
+    not_first_column: Mask,
+    not_last_column: Mask,
+    squares: Mask,
+    owners: [Mask; Player::MAX_PLAYERS],
+    values: [Mask; 3],
+}
+
+impl Bitboards {
+    /// The largest number of squares a board can have to be packed.
+    pub(crate) const MAX_SQUARES: usize = Mask::BITS as usize;
+
+    /// Creates the bitboards of an empty board, or `None` if it has too many squares.
+    pub(crate) fn new(dimensions: Dimensions) -> Option<Self> {
+        let count = dimensions.rows() * dimensions.columns();
+        if count > Self::MAX_SQUARES {
+            return None;
+        }
+
+        let squares = Mask::MAX >> (Self::MAX_SQUARES - count);
+        let first_column =
+            (0..dimensions.rows()).fold(0, |mask, row| mask | 1 << (row * dimensions.columns()));
+        let last_column = first_column << (dimensions.columns() - 1);
+
+        Some(Self {
+            dimensions,
+            not_first_column: squares & !first_column,
+            not_last_column: squares & !last_column,
+            squares,
+            owners: [0; Player::MAX_PLAYERS],
+            values: [0; 3],
+        })
+    }
+
+    /// Returns the bit index of `coordinates`.
+    pub(crate) fn index(&self, coordinates: Coordinates) -> usize {
+        coordinates.row() * self.dimensions.columns() + coordinates.column()
+    }
+
+    /// Returns the coordinates of the square at bit `index`.
+    pub(crate) fn coordinates(&self, index: usize) -> Coordinates {
+        let columns = self.dimensions.columns();
+        Coordinates::new(index / columns, index % columns, self.dimensions)
+    }
+
+    /// Returns the squares `player` occupies.
+    pub(crate) fn owned_by(&self, player: Player) -> Mask {
+        self.owners[player.index()]
+    }
+
+    /// Returns the square at bit `index`.
+    pub(crate) fn square(&self, index: usize) -> Square {
+        let bit = 1 << index;
+        match Player::ALL
+            .into_iter()
+            .find(|player| self.owners[player.index()] & bit != 0)
+        {
+            Some(owner) => {
+                let value = (0..self.values.len())
+                    .filter(|plane| self.values[*plane] & bit != 0)
+                    .map(|plane| 1 << plane)
+                    .sum();
+                Square::occupied(owner, value)
+            }
+            None => Square::empty(),
+        }
+    }
+
+    /// Replaces the square at bit `index`.
+    pub(crate) fn set(&mut self, index: usize, square: Square) {
+        let bit = 1 << index;
+        self.clear(bit);
+        if let Some(owner) = square.owner() {
+            self.owners[owner.index()] |= bit;
+            for (plane, mask) in self.values.iter_mut().enumerate() {
+                if square.value() & (1 << plane) != 0 {
+                    *mask |= bit;
+                }
+            }
+        }
+    }
+
+    /// Resolves `player` incrementing the square at bit `index`, pops and chain
+    /// reactions included, and returns the squares that changed.
+    ///
+    /// Pops are resolved in the same order as the grid does, so both give the same
+    /// board, and without allocating. Returns `None` if a square's value or the
+    /// chain reaction grew too large to be packed, in which case the bitboards are
+    /// left in an unspecified state.
+    pub(crate) fn resolve_move(&mut self, index: usize, player: Player) -> Option<Mask> {
+        let placement = 1 << index;
+        self.increment(placement)?;
+
+        let mut changed = placement;
+        let mut pops = PopStack::new();
+        if self.popping() & placement != 0 {
+            pops.push(placement)?;
+        }
+
+        while let Some(pop) = pops.pop() {
+            // The square that pops becomes empty
+            self.clear(pop);
+
+            let neighbors = self.neighbors(pop);
+            let all_neighbors = neighbors.iter().fold(0, |mask, neighbor| mask | neighbor);
+            self.capture(all_neighbors, player);
+            self.increment(all_neighbors)?;
+            changed |= all_neighbors;
+
+            // Neighbors that pop are queued in the grid's neighbor order
+            let popping = self.popping();
+            for neighbor in neighbors {
+                if neighbor & popping != 0 {
+                    pops.push(neighbor)?;
+                }
+            }
+        }
+
+        Some(changed)
+    }
+
+    // Returns the north, south, west and east neighbors of a single square, in the
+    // order `Coordinates::neighbors` lists them. Missing neighbors are `0`.
+    fn neighbors(&self, square: Mask) -> [Mask; 4] {
+        let columns = self.dimensions.columns();
+        [
+            square >> columns,
+            (square << columns) & self.squares,
+            (square & self.not_first_column) >> 1,
+            (square & self.not_last_column) << 1,
+        ]
+    }
+
+    // Empties every square in `mask`.
+    fn clear(&mut self, mask: Mask) {
+        for owner in &mut self.owners {
+            *owner &= !mask;
+        }
+        for plane in &mut self.values {
+            *plane &= !mask;
+        }
+    }
+
+    // Gives every square in `mask` to `player`, keeping its value.
+    fn capture(&mut self, mask: Mask, player: Player) {
+        for owner in &mut self.owners {
+            *owner &= !mask;
+        }
+        self.owners[player.index()] |= mask;
+    }
+
+    // Adds one to the value of every square in `mask`, failing if one reaches `8`.
+    fn increment(&mut self, mask: Mask) -> Option<()> {
+        let mut carry = mask;
+        for plane in &mut self.values {
+            let next_carry = *plane & carry;
+            *plane ^= carry;
+            carry = next_carry;
+        }
+        (carry == 0).then_some(())
+    }
+
+    // Returns the squares with a value of `4` or more.
+    fn popping(&self) -> Mask {
+        self.values[2]
+    }
+}
+
+/// The squares waiting to pop, most recent last, kept on the stack.
+struct PopStack {
+    indexes: [u8; PopStack::CAPACITY],
+    len: usize,
+}
+
+impl PopStack {
+    const CAPACITY: usize = 64;
+
+    fn new() -> Self {
+        Self {
+            indexes: [0; Self::CAPACITY],
+            len: 0,
+        }
+    }
+
+    // Queues the single square in `square`, failing if the stack is full.
+    fn push(&mut self, square: Mask) -> Option<()> {
+        *self.indexes.get_mut(self.len)? = square.trailing_zeros() as u8;
+        self.len += 1;
+        Some(())
+    }
+
+    fn pop(&mut self) -> Option<Mask> {
+        self.len = self.len.checked_sub(1)?;
+        Some(1 << self.indexes[self.len])
+    }
+}
+
+/// Returns the bit index of every square in `mask`, in ascending order.
+pub(crate) fn indexes(mut mask: Mask) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let index = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(index)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn too_many_squares() {
+        assert!(Bitboards::new(Dimensions::new(8, 16)).is_some());
+        assert!(Bitboards::new(Dimensions::new(11, 12)).is_none());
+    }
+
+    #[test]
+    fn set_and_read_squares() {
+        let dimensions = Dimensions::new(3, 4);
+        let mut bitboards = Bitboards::new(dimensions).unwrap();
+        let coordinates = Coordinates::new(2, 1, dimensions);
+        let index = bitboards.index(coordinates);
+        assert_eq!(bitboards.coordinates(index), coordinates);
+
+        bitboards.set(index, Square::occupied(Player::Green, 3));
+        assert_eq!(bitboards.square(index), Square::occupied(Player::Green, 3));
+        assert_eq!(bitboards.owned_by(Player::Green), 1 << index);
+
+        bitboards.set(index, Square::occupied(Player::Blue, 2));
+        assert_eq!(bitboards.square(index), Square::occupied(Player::Blue, 2));
+        assert_eq!(bitboards.owned_by(Player::Green), 0);
+
+        bitboards.set(index, Square::empty());
+        assert_eq!(bitboards.square(index), Square::empty());
+    }
+
+    #[test]
+    fn neighbors_stay_on_the_board() {
+        let dimensions = Dimensions::new(3, 4);
+        let bitboards = Bitboards::new(dimensions).unwrap();
+        for coordinates in dimensions.coordinates() {
+            let neighbors: Vec<Coordinates> = bitboards
+                .neighbors(1 << bitboards.index(coordinates))
+                .into_iter()
+                .filter(|neighbor| *neighbor != 0)
+                .map(|neighbor| bitboards.coordinates(neighbor.trailing_zeros() as usize))
+                .collect();
+            assert_eq!(
+                neighbors,
+                coordinates.neighbors(dimensions).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn indexes_in_ascending_order() {
+        assert_eq!(indexes(0b1010_0001).collect::<Vec<_>>(), [0, 5, 7]);
+        assert_eq!(indexes(0).count(), 0);
+        assert_eq!(indexes(1 << 127).collect::<Vec<_>>(), [127]);
+    }
+}
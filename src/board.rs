@@ -1,68 +1,271 @@
 //! Manages the game state and enforces the rules of Color Wars.
+use std::hash::{Hash, Hasher};
+
 use crate::{
-    BOARD_COLUMN_SIZE, BOARD_ROW_SIZE, coordinates::Coordinates, player::Player, square::Square,
+    bitboard::{self, Bitboards, Mask},
+    coordinates::Coordinates,
+    dimensions::Dimensions,
+    grid::Grid,
+    move_error::MoveError,
+    move_trace::{MoveEvent, MoveTrace},
+    player::Player,
+    square::Square,
+    zobrist,
 };
 
-/// A type alias for the 2D array representing the game grid.
-pub type Grid = [[Square; BOARD_COLUMN_SIZE]; BOARD_ROW_SIZE];
-
 /// Represents the entire game board and its current state.
 ///
-/// This struct holds the grid of squares and tracks whose turn it is.
+/// This struct holds the grid of squares, the players taking part and whose turn it is.
 /// It is the central point for all game logic, such as validating and applying moves.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+///
+/// # Serialization
+///
+/// With the `serde` feature enabled, a board is serialized as an object holding its
+/// `dimensions`, the number of `players`, the player whose `turn` it is and the `grid`
+/// as a list of rows. Empty squares are `null`, occupied squares hold their `owner`
+/// and `value`. This shape is stable, for example a 2x2 board:
+///
+/// ```json
+/// {
+///   "dimensions": { "rows": 2, "columns": 2 },
+///   "players": 2,
+///   "turn": "Red",
+///   "grid": [
+///     [{ "owner": "Red", "value": 3 }, null],
+///     [null, { "owner": "Blue", "value": 3 }]
+///   ]
+/// }
+/// ```
+///
+/// Deserializing checks the board follows the rules, e.g. that every value is from
+/// `1` to `3` and every owner takes part in the game.
+///
+/// # Representation
+///
+/// Boards of up to 128 squares also keep a packed copy of the grid, with one bitmask
+/// per player and per bit of the square values. Move generation, elimination checks
+/// and pop resolution use it when available, larger boards fall back to scanning
+/// the grid. Both give the same results.
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "BoardFields", into = "BoardFields")
+)]
 pub struct Board {
     grid: Grid,
     turn: Player,
+    player_count: usize,
+    hash_key: u64,
+    bitboards: Option<Bitboards>,
+}
+
+/// Boards are equal when their positions are, whichever representation they use.
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash_key == other.hash_key
+            && self.turn == other.turn
+            && self.player_count == other.player_count
+            && self.grid == other.grid
+    }
+}
+
+/// Boards are hashed by their Zobrist key, which is kept up to date by every move.
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash_key);
+    }
 }
 
 impl Board {
-    /// Creates a new, empty board for a given player's turn.
-    pub fn new(current_turn: Player) -> Self {
+    /// Creates a new, empty board of the given size for a game of `player_count`
+    /// players, with `current_turn` to move.
+    ///
+    /// # Panics
+    /// Panics if `player_count` is outside `Player::MIN_PLAYERS..=Player::MAX_PLAYERS`,
+    /// or `current_turn` does not take part in a game of that many players.
+    pub fn new(dimensions: Dimensions, player_count: usize, current_turn: Player) -> Self {
+        assert!((Player::MIN_PLAYERS..=Player::MAX_PLAYERS).contains(&player_count));
+        assert!(current_turn.index() < player_count);
         Board {
-            grid: [[Square::empty(); BOARD_COLUMN_SIZE]; BOARD_ROW_SIZE],
+            grid: Grid::new(dimensions),
             turn: current_turn,
+            player_count,
+            hash_key: zobrist::dimensions_key(dimensions)
+                ^ zobrist::turn_key(current_turn)
+                ^ zobrist::player_count_key(player_count),
+            bitboards: Bitboards::new(dimensions),
         }
     }
 
-    /// Returns a copy of the current grid state.
-    pub fn grid(&self) -> Grid {
-        self.grid
+    /// Returns the current grid state.
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    // Lets the rest of the crate change a square, e.g. to build a parsed position,
+    // while keeping the hash key up to date.
+    pub(crate) fn set_square(&mut self, coordinates: Coordinates, square: Square) {
+        self.toggle_square_key(coordinates);
+        self.grid[coordinates.row()][coordinates.column()] = square;
+        self.square_changed(coordinates);
+    }
+
+    /// Returns a copy of the board that only uses the grid representation.
+    ///
+    /// Both representations play the same, this exists to compare their speed.
+    #[doc(hidden)]
+    pub fn unpacked(&self) -> Board {
+        Board {
+            bitboards: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the Zobrist hash key of the position.
+    ///
+    /// Equal positions always have the same key, and different positions almost
+    /// never do. The key is updated as moves are made, so reading it is O(1),
+    /// which makes it cheap to use for transposition lookups and repetition detection.
+    pub fn hash_key(&self) -> u64 {
+        self.hash_key
+    }
+
+    // Adds the square at `coordinates` to the hash key, or removes it if it was added.
+    fn toggle_square_key(&mut self, coordinates: Coordinates) {
+        let index = coordinates.row() * self.dimensions().columns() + coordinates.column();
+        self.hash_key ^=
+            zobrist::square_key(index, self.grid[coordinates.row()][coordinates.column()]);
+    }
+
+    // Brings the hash key and the bitboards up to date after the square at
+    // `coordinates` changed. Its old key must have been removed beforehand.
+    fn square_changed(&mut self, coordinates: Coordinates) {
+        self.toggle_square_key(coordinates);
+        let square = self.grid[coordinates.row()][coordinates.column()];
+        if let Some(bitboards) = &mut self.bitboards {
+            bitboards.set(bitboards.index(coordinates), square);
+        }
+    }
+
+    /// Returns the number of rows and columns of the board.
+    pub fn dimensions(&self) -> Dimensions {
+        self.grid.dimensions()
     }
 
     /// Returns the player whose turn it is.
+    ///
+    /// Turns rotate in `Player::ALL` order, skipping eliminated players.
+    /// Once the game is over this is the winner.
     pub fn turn(&self) -> Player {
         self.turn
     }
 
-    /// An alternate constructor to set up the initial game state with two starting pieces.
+    /// Returns the players taking part in the game, in turn order.
+    pub fn players(&self) -> &'static [Player] {
+        &Player::ALL[..self.player_count]
+    }
+
+    /// Returns the number of squares `player` currently occupies.
+    pub fn square_count(&self, player: Player) -> usize {
+        if let Some(bitboards) = &self.bitboards {
+            return bitboards.owned_by(player).count_ones() as usize;
+        }
+        self.grid
+            .rows()
+            .flatten()
+            .filter(|square| square.owner() == Some(player))
+            .count()
+    }
+
+    /// Checks whether `player` has lost all of their squares.
+    ///
+    /// Players can only gain squares by popping their own, so once eliminated
+    /// a player stays out of the game.
+    pub fn is_eliminated(&self, player: Player) -> bool {
+        self.square_count(player) == 0
+    }
+
+    /// Returns whether the player to move has no squares while another player has,
+    /// a position no game can reach.
+    pub(crate) fn is_turn_eliminated(&self) -> bool {
+        self.is_eliminated(self.turn)
+            && self
+                .players()
+                .iter()
+                .any(|player| !self.is_eliminated(*player))
+    }
+
+    /// An alternate constructor to set up the initial game state with one starting piece
+    /// per player.
     ///
-    /// Player Red always gets the first turn after setup.
-    pub fn setup(red_placement: Coordinates, blue_placement: Coordinates) -> Self {
-        let mut board = Board::new(Player::Red);
+    /// `placements` holds each player's starting square in turn order, so its length
+    /// decides how many players take part. Player Red always gets the first turn after setup.
+    ///
+    /// # Panics
+    /// Panics if the placements are not valid, see [`Board::try_setup`] to handle
+    /// those cases without panicking.
+    pub fn setup(dimensions: Dimensions, placements: &[Coordinates]) -> Self {
+        match Self::try_setup(dimensions, placements) {
+            Ok(board) => board,
+            Err(error) => panic!("invalid setup: {error}"),
+        }
+    }
 
-        board.grid[red_placement.row()][red_placement.column()] = Square::occupied(Player::Red, 3);
+    /// Sets up the initial game state like [`Board::setup`], reporting invalid placements.
+    ///
+    /// # Errors
+    /// Returns `MoveError::InvalidPlayerCount` if there are not between 2 and 4 placements,
+    /// `MoveError::OutOfBounds` if a placement lies outside `dimensions`,
+    /// or `MoveError::SameStartingSquare` if two players chose the same square.
+    pub fn try_setup(
+        dimensions: Dimensions,
+        placements: &[Coordinates],
+    ) -> Result<Self, MoveError> {
+        if !(Player::MIN_PLAYERS..=Player::MAX_PLAYERS).contains(&placements.len()) {
+            return Err(MoveError::InvalidPlayerCount(placements.len()));
+        }
 
-        board.grid[blue_placement.row()][blue_placement.column()] =
-            Square::occupied(Player::Blue, 3);
+        let mut board = Board::new(dimensions, placements.len(), Player::Red);
+
+        for (player, placement) in Player::ALL.into_iter().zip(placements) {
+            if !dimensions.contains(*placement) {
+                return Err(MoveError::OutOfBounds {
+                    row: placement.row(),
+                    column: placement.column(),
+                    dimensions,
+                });
+            }
 
-        board
+            if board.grid[placement.row()][placement.column()]
+                .owner()
+                .is_some()
+            {
+                return Err(MoveError::SameStartingSquare(*placement));
+            }
+            board.set_square(*placement, Square::occupied(player, 3));
+        }
+
+        Ok(board)
     }
 
     /// Returns a list of all squares the current player can choose for a move.
     /// A move is represented by the `Coordinates` of a square that the player occupies.
     pub fn get_valid_moves(&self) -> Vec<Coordinates> {
-        let mut moves = Vec::new();
-        for row in 0..BOARD_ROW_SIZE {
-            for column in 0..BOARD_COLUMN_SIZE {
-                if let Some(player) = self.grid[row][column].owner()
-                    && player == self.turn
-                {
-                    moves.push(Coordinates::new(row, column));
-                }
-            }
+        self.valid_moves().collect()
+    }
+
+    /// Returns an iterator over the same moves as [`Board::get_valid_moves`], in the
+    /// same order, without allocating.
+    pub fn valid_moves(&self) -> ValidMoves<'_> {
+        ValidMoves {
+            board: self,
+            mask: self
+                .bitboards
+                .as_ref()
+                .map_or(0, |bitboards| bitboards.owned_by(self.turn)),
+            next_index: 0,
         }
-        moves
     }
 
     /// Applies a move to the board, handling pops and chain reactions.
@@ -70,63 +273,336 @@ impl Board {
     /// This method takes the coordinates of a player's square to increment. It processes
     /// the initial value increase and any subsequent chain reaction of "pops."
     /// It returns a new `Board` representing the state after the move is completed
-    /// and the turn has been passed to the opponent.
+    /// and the turn has been passed to the next player still in the game.
     ///
     /// # Panics
     /// Panics if the provided `placement` is not a valid move for the current player.
+    /// Use [`Board::try_make_move`] to handle invalid moves without panicking.
     pub fn make_move(&self, placement: Coordinates) -> Board {
-        assert!(self.get_valid_moves().contains(&placement));
+        match self.try_make_move(placement) {
+            Ok(board) => board,
+            Err(error) => panic!("invalid move: {error}"),
+        }
+    }
+
+    /// Applies a move to the board like [`Board::make_move`], reporting invalid moves.
+    ///
+    /// # Errors
+    /// Returns a `MoveError` describing why `placement` is not a valid move for the
+    /// current player. The board is left untouched in that case.
+    pub fn try_make_move(&self, placement: Coordinates) -> Result<Board, MoveError> {
+        self.validate_move(placement)?;
+        Ok(self.resolve_move(placement, None))
+    }
+
+    /// Applies a move like [`Board::make_move`], also returning the ordered trace of
+    /// every increment, pop and capture that happened during the chain reaction.
+    ///
+    /// # Panics
+    /// Panics if the provided `placement` is not a valid move for the current player.
+    pub fn make_move_traced(&self, placement: Coordinates) -> (Board, MoveTrace) {
+        match self.try_make_move_traced(placement) {
+            Ok(result) => result,
+            Err(error) => panic!("invalid move: {error}"),
+        }
+    }
+
+    /// Applies a move like [`Board::try_make_move`], also returning the ordered trace of
+    /// every increment, pop and capture that happened during the chain reaction.
+    ///
+    /// # Errors
+    /// Returns a `MoveError` describing why `placement` is not a valid move for the
+    /// current player.
+    pub fn try_make_move_traced(
+        &self,
+        placement: Coordinates,
+    ) -> Result<(Board, MoveTrace), MoveError> {
+        self.validate_move(placement)?;
+
+        let mut trace = MoveTrace::new();
+        let new_board = self.resolve_move(placement, Some(&mut trace));
+        Ok((new_board, trace))
+    }
+
+    // Resolves an already validated move, recording events only when a trace is given
+    // so the engine's search pays nothing for it.
+    fn resolve_move(&self, placement: Coordinates, mut trace: Option<&mut MoveTrace>) -> Board {
+        if trace.is_none()
+            && let Some(new_board) = self.resolve_move_packed(placement)
+        {
+            return new_board;
+        }
 
         let mut new_board = self.clone();
+        new_board.toggle_square_key(placement);
         new_board.grid[placement.row()][placement.column()].increment_value();
+        new_board.square_changed(placement);
+
+        let value = new_board.grid[placement.row()][placement.column()].value();
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.push(MoveEvent::Increment {
+                coordinates: placement,
+                value,
+                wave: 0,
+            });
+        }
 
+        // Each pending pop remembers the wave of the chain reaction it belongs to
         let mut pops = vec![];
-        if new_board.grid[placement.row()][placement.column()].value() >= 4 {
-            pops.push(placement);
+        if value >= 4 {
+            pops.push((placement, 1));
         }
 
         // Process all pops in the chain reaction
-        while let Some(pop_location) = pops.pop() {
+        while let Some((pop_location, wave)) = pops.pop() {
             // The square that pops becomes empty
+            new_board.toggle_square_key(pop_location);
             new_board.grid[pop_location.row()][pop_location.column()].reset_square();
+            new_board.square_changed(pop_location);
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.push(MoveEvent::Pop {
+                    coordinates: pop_location,
+                    wave,
+                });
+            }
 
-            for neighbor_position in pop_location.neighbors() {
+            for neighbor_position in pop_location.neighbors(self.dimensions()) {
+                new_board.toggle_square_key(neighbor_position);
                 let square =
                     &mut new_board.grid[neighbor_position.row()][neighbor_position.column()];
 
+                let previous_owner = square.owner();
                 square.set_owner(self.turn);
                 square.increment_value();
 
+                if let Some(trace) = trace.as_deref_mut() {
+                    if previous_owner != Some(self.turn) {
+                        trace.push(MoveEvent::Capture {
+                            coordinates: neighbor_position,
+                            previous_owner,
+                            wave,
+                        });
+                    }
+                    trace.push(MoveEvent::NeighborIncrement {
+                        coordinates: neighbor_position,
+                        value: square.value(),
+                        wave,
+                    });
+                }
+
                 // If the neighbor pops, add it to the list to be processed
                 if square.value() >= 4 {
-                    pops.push(neighbor_position);
+                    pops.push((neighbor_position, wave + 1));
                 }
+                new_board.square_changed(neighbor_position);
             }
         }
 
-        new_board.turn = self.turn.opponent();
+        new_board.pass_turn();
         new_board
     }
 
+    // Resolves an already validated move with bit operations on the packed
+    // representation, or returns `None` if the board does not have one.
+    fn resolve_move_packed(&self, placement: Coordinates) -> Option<Board> {
+        let mut bitboards = self.bitboards.clone()?;
+        let changed = bitboards.resolve_move(bitboards.index(placement), self.turn)?;
+
+        let mut new_board = self.clone();
+        for index in bitboard::indexes(changed) {
+            let coordinates = bitboards.coordinates(index);
+            new_board.toggle_square_key(coordinates);
+            new_board.grid[coordinates.row()][coordinates.column()] = bitboards.square(index);
+            new_board.toggle_square_key(coordinates);
+        }
+        new_board.bitboards = Some(bitboards);
+
+        new_board.pass_turn();
+        Some(new_board)
+    }
+
+    // Gives the turn to the next player still in the game.
+    fn pass_turn(&mut self) {
+        let next_turn = self.next_turn();
+        self.hash_key ^= zobrist::turn_key(self.turn) ^ zobrist::turn_key(next_turn);
+        self.turn = next_turn;
+    }
+
+    // Finds the next player in turn order who still has squares, wrapping around to
+    // the current player when everyone else has been eliminated.
+    fn next_turn(&self) -> Player {
+        let mut player = self.turn;
+        for _ in 0..self.player_count {
+            player = player.next(self.player_count);
+            if !self.is_eliminated(player) {
+                return player;
+            }
+        }
+        self.turn
+    }
+
+    // Checks that `placement` is a square the current player may increment.
+    fn validate_move(&self, placement: Coordinates) -> Result<(), MoveError> {
+        let dimensions = self.dimensions();
+        if !dimensions.contains(placement) {
+            return Err(MoveError::OutOfBounds {
+                row: placement.row(),
+                column: placement.column(),
+                dimensions,
+            });
+        }
+        if self.is_game_over() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+
+        match self.grid[placement.row()][placement.column()].owner() {
+            None => Err(MoveError::EmptySquare(placement)),
+            Some(owner) if owner != self.turn => Err(MoveError::NotYourSquare {
+                coordinates: placement,
+                owner,
+            }),
+            Some(_) => Ok(()),
+        }
+    }
+
     /// Checks if the game has ended.
     ///
-    /// The game is over if either player has no squares left on the board.
+    /// The game is over once only one player has squares left on the board.
     pub fn is_game_over(&self) -> bool {
-        let mut red_squares = 0;
-        let mut blue_squares = 0;
-        for row in 0..BOARD_ROW_SIZE {
-            for column in 0..BOARD_COLUMN_SIZE {
-                if let Some(owner) = self.grid[row][column].owner() {
-                    if owner == Player::Red {
-                        red_squares += 1;
-                    } else {
-                        blue_squares += 1;
-                    }
+        let remaining_players = self
+            .players()
+            .iter()
+            .filter(|player| !self.is_eliminated(**player))
+            .count();
+
+        remaining_players == 1
+    }
+
+    /// Returns the last player standing, or `None` while the game is still going.
+    pub fn winner(&self) -> Option<Player> {
+        if !self.is_game_over() {
+            return None;
+        }
+        self.players()
+            .iter()
+            .copied()
+            .find(|player| !self.is_eliminated(*player))
+    }
+}
+
+/// An iterator over the moves of the player to move, see [`Board::valid_moves`].
+#[derive(Debug, Clone)]
+pub struct ValidMoves<'a> {
+    board: &'a Board,
+    // The player's squares not visited yet, on boards with bitboards
+    mask: Mask,
+    // The next square to scan, on boards without bitboards
+    next_index: usize,
+}
+
+impl Iterator for ValidMoves<'_> {
+    type Item = Coordinates;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(bitboards) = &self.board.bitboards {
+            let index = bitboard::indexes(self.mask).next()?;
+            self.mask &= self.mask - 1;
+            return Some(bitboards.coordinates(index));
+        }
+
+        let dimensions = self.board.dimensions();
+        while self.next_index < dimensions.area() {
+            let coordinates = Coordinates::new(
+                self.next_index / dimensions.columns(),
+                self.next_index % dimensions.columns(),
+                dimensions,
+            );
+            self.next_index += 1;
+            if self.board.grid[coordinates.row()][coordinates.column()].owner()
+                == Some(self.board.turn)
+            {
+                return Some(coordinates);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.board.bitboards.is_some() {
+            let count = self.mask.count_ones() as usize;
+            return (count, Some(count));
+        }
+        (0, Some(self.board.dimensions().area() - self.next_index))
+    }
+}
+
+// The serialized form of a `Board`, checked before it becomes one.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BoardFields {
+    dimensions: Dimensions,
+    players: usize,
+    turn: Player,
+    grid: Grid,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<BoardFields> for Board {
+    type Error = String;
+
+    fn try_from(fields: BoardFields) -> Result<Self, Self::Error> {
+        if fields.grid.dimensions() != fields.dimensions {
+            return Err(format!(
+                "the grid is {} but the dimensions are {}",
+                fields.grid.dimensions(),
+                fields.dimensions
+            ));
+        }
+        if !(Player::MIN_PLAYERS..=Player::MAX_PLAYERS).contains(&fields.players) {
+            return Err(MoveError::InvalidPlayerCount(fields.players).to_string());
+        }
+        if fields.turn.index() >= fields.players {
+            return Err(format!("{} does not take part in the game", fields.turn));
+        }
+
+        for coordinates in fields.dimensions.coordinates() {
+            let square = fields.grid[coordinates.row()][coordinates.column()];
+            if let Some(owner) = square.owner() {
+                if owner.index() >= fields.players {
+                    return Err(format!("{owner} does not take part in the game"));
+                }
+                if !(1..=3).contains(&square.value()) {
+                    return Err(format!(
+                        "{coordinates} has a value of {}, expected 1 to 3",
+                        square.value()
+                    ));
                 }
             }
         }
 
-        (red_squares > 0 || blue_squares > 0) && (red_squares == 0 || blue_squares == 0)
+        let mut board = Board::new(fields.dimensions, fields.players, fields.turn);
+        for coordinates in fields.dimensions.coordinates() {
+            board.set_square(
+                coordinates,
+                fields.grid[coordinates.row()][coordinates.column()],
+            );
+        }
+        if board.is_turn_eliminated() {
+            return Err(format!("{} has no squares left to play", fields.turn));
+        }
+        Ok(board)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Board> for BoardFields {
+    fn from(board: Board) -> Self {
+        BoardFields {
+            dimensions: board.dimensions(),
+            players: board.player_count,
+            turn: board.turn,
+            grid: board.grid,
+        }
     }
 }
 
@@ -134,9 +610,31 @@ impl Board {
 mod tests {
     use super::*;
 
+    // Computes the hash key of `board` from scratch.
+    fn compute_hash_key(board: &Board) -> u64 {
+        board.dimensions().coordinates().enumerate().fold(
+            zobrist::dimensions_key(board.dimensions())
+                ^ zobrist::turn_key(board.turn)
+                ^ zobrist::player_count_key(board.player_count),
+            |key, (index, coordinates)| {
+                key ^ zobrist::square_key(
+                    index,
+                    board.grid[coordinates.row()][coordinates.column()],
+                )
+            },
+        )
+    }
+
     // Helper function to create a board for testing
     fn setup_test_board() -> Board {
-        Board::setup(Coordinates::new(0, 0), Coordinates::new(4, 4))
+        let dimensions = Dimensions::default();
+        Board::setup(
+            dimensions,
+            &[
+                Coordinates::new(0, 0, dimensions),
+                Coordinates::new(4, 4, dimensions),
+            ],
+        )
     }
 
     #[test]
@@ -152,18 +650,26 @@ mod tests {
     #[test]
     fn test_get_valid_moves() {
         let board = setup_test_board();
-        assert_eq!(board.get_valid_moves(), vec![Coordinates::new(0, 0)]);
-        let board_p2 = Board {
+        let dimensions = board.dimensions();
+        assert_eq!(
+            board.get_valid_moves(),
+            vec![Coordinates::new(0, 0, dimensions)]
+        );
+        let mut board_p2 = Board {
             turn: Player::Blue,
             ..board
         };
-        assert_eq!(board_p2.get_valid_moves(), vec![Coordinates::new(4, 4)]);
+        board_p2.hash_key = compute_hash_key(&board_p2);
+        assert_eq!(
+            board_p2.get_valid_moves(),
+            vec![Coordinates::new(4, 4, dimensions)]
+        );
     }
 
     #[test]
     fn test_simple_pop() {
         let board = setup_test_board(); // Red at (0,0) with value 3
-        let new_board = board.make_move(Coordinates::new(0, 0));
+        let new_board = board.make_move(Coordinates::new(0, 0, board.dimensions()));
 
         // 1. The popped square (0,0) should be empty
         assert_eq!(new_board.grid[0][0], Square::empty());
@@ -182,9 +688,12 @@ mod tests {
     fn test_pop_captures_opponent() {
         let mut board = setup_test_board();
         // Manually place a Blue square next to the Red one
-        board.grid[0][1] = Square::occupied(Player::Blue, 2);
+        board.set_square(
+            Coordinates::new(0, 1, board.dimensions()),
+            Square::occupied(Player::Blue, 2),
+        );
 
-        let new_board = board.make_move(Coordinates::new(0, 0));
+        let new_board = board.make_move(Coordinates::new(0, 0, board.dimensions()));
 
         // The neighbor at (0,1) should now be Red with value 3 (2+1)
         assert_eq!(new_board.grid[0][1].owner(), Some(Player::Red));
@@ -193,11 +702,21 @@ mod tests {
 
     #[test]
     fn test_chain_reaction() {
-        let mut board = Board::new(Player::Red);
-        board.grid[0][0] = Square::occupied(Player::Red, 3);
-        board.grid[0][1] = Square::occupied(Player::Red, 3); // This will pop from the first pop
+        let mut board = Board::new(Dimensions::default(), 2, Player::Red);
+        board.set_square(
+            Coordinates::new(0, 0, board.dimensions()),
+            Square::occupied(Player::Red, 3),
+        );
+        board.set_square(
+            Coordinates::new(0, 1, board.dimensions()),
+            Square::occupied(Player::Red, 3),
+        ); // This will pop from the first pop
+        board.set_square(
+            Coordinates::new(4, 4, board.dimensions()),
+            Square::occupied(Player::Blue, 1),
+        ); // Keeps the game going
 
-        let new_board = board.make_move(Coordinates::new(0, 0));
+        let new_board = board.make_move(Coordinates::new(0, 0, board.dimensions()));
 
         // (0,0) pops, increments (0,1) to 4, which also pops.
         // Final state: (0,0) is red and value is 1, (0,1) is empty, and (0,2) and (1, 1) are red and values are 1
@@ -211,11 +730,474 @@ mod tests {
         assert_eq!(new_board.grid[1][1].value(), 1);
     }
 
+    #[test]
+    fn test_make_move_traced_simple_pop() {
+        let board = setup_test_board();
+        let dimensions = board.dimensions();
+        let corner = Coordinates::new(0, 0, dimensions);
+        let (new_board, trace) = board.make_move_traced(corner);
+
+        assert_eq!(new_board, board.make_move(corner));
+        assert_eq!(
+            trace.events(),
+            [
+                MoveEvent::Increment {
+                    coordinates: corner,
+                    value: 4,
+                    wave: 0
+                },
+                MoveEvent::Pop {
+                    coordinates: corner,
+                    wave: 1
+                },
+                MoveEvent::Capture {
+                    coordinates: Coordinates::new(1, 0, dimensions),
+                    previous_owner: None,
+                    wave: 1
+                },
+                MoveEvent::NeighborIncrement {
+                    coordinates: Coordinates::new(1, 0, dimensions),
+                    value: 1,
+                    wave: 1
+                },
+                MoveEvent::Capture {
+                    coordinates: Coordinates::new(0, 1, dimensions),
+                    previous_owner: None,
+                    wave: 1
+                },
+                MoveEvent::NeighborIncrement {
+                    coordinates: Coordinates::new(0, 1, dimensions),
+                    value: 1,
+                    wave: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_make_move_traced_chain_reaction() {
+        let mut board = Board::new(Dimensions::default(), 2, Player::Red);
+        board.set_square(
+            Coordinates::new(0, 0, board.dimensions()),
+            Square::occupied(Player::Red, 3),
+        );
+        board.set_square(
+            Coordinates::new(0, 1, board.dimensions()),
+            Square::occupied(Player::Blue, 3),
+        );
+        board.set_square(
+            Coordinates::new(0, 2, board.dimensions()),
+            Square::occupied(Player::Blue, 2),
+        );
+
+        let dimensions = board.dimensions();
+        let (new_board, trace) = board.make_move_traced(Coordinates::new(0, 0, dimensions));
+
+        assert_eq!(
+            new_board,
+            board.make_move(Coordinates::new(0, 0, dimensions))
+        );
+        assert_eq!(trace.pop_count(), 2);
+        assert_eq!(trace.wave_count(), 2);
+        assert!(trace.events().contains(&MoveEvent::Capture {
+            coordinates: Coordinates::new(0, 1, dimensions),
+            previous_owner: Some(Player::Blue),
+            wave: 1
+        }));
+        assert!(trace.events().contains(&MoveEvent::Capture {
+            coordinates: Coordinates::new(0, 2, dimensions),
+            previous_owner: Some(Player::Blue),
+            wave: 2
+        }));
+        assert!(trace.events().contains(&MoveEvent::NeighborIncrement {
+            coordinates: Coordinates::new(0, 2, dimensions),
+            value: 3,
+            wave: 2
+        }));
+    }
+
+    #[test]
+    fn test_try_make_move_traced_invalid() {
+        let board = setup_test_board();
+        assert_eq!(
+            board.try_make_move_traced(Coordinates::new(2, 2, board.dimensions())),
+            Err(MoveError::EmptySquare(Coordinates::new(
+                2,
+                2,
+                board.dimensions()
+            )))
+        );
+    }
+
     #[test]
     fn test_is_game_over() {
-        let mut board = Board::new(Player::Red);
+        let mut board = Board::new(Dimensions::default(), 2, Player::Red);
         assert!(!board.is_game_over()); // Empty board is not game over
-        board.grid[0][0] = Square::occupied(Player::Red, 1);
+        board.set_square(
+            Coordinates::new(0, 0, board.dimensions()),
+            Square::occupied(Player::Red, 1),
+        );
         assert!(board.is_game_over()); // Only one player has squares
     }
+
+    #[test]
+    fn test_non_square_board() {
+        let dimensions = Dimensions::new(3, 7);
+        let board = Board::setup(
+            dimensions,
+            &[
+                Coordinates::new(2, 6, dimensions),
+                Coordinates::new(0, 0, dimensions),
+            ],
+        );
+        let new_board = board.make_move(Coordinates::new(2, 6, dimensions));
+
+        // The corner pop only reaches the two neighbors that are on the board
+        assert_eq!(new_board.grid[2][6], Square::empty());
+        assert_eq!(new_board.grid[1][6], Square::occupied(Player::Red, 1));
+        assert_eq!(new_board.grid[2][5], Square::occupied(Player::Red, 1));
+        assert_eq!(new_board.get_valid_moves().len(), 1);
+    }
+
+    #[test]
+    fn test_try_setup_errors() {
+        let dimensions = Dimensions::default();
+        let corner = Coordinates::new(0, 0, dimensions);
+        let outside = Coordinates::new(7, 7, Dimensions::new(8, 8));
+
+        assert_eq!(
+            Board::try_setup(dimensions, &[corner, corner]),
+            Err(MoveError::SameStartingSquare(corner))
+        );
+        assert_eq!(
+            Board::try_setup(dimensions, &[corner, outside]),
+            Err(MoveError::OutOfBounds {
+                row: 7,
+                column: 7,
+                dimensions
+            })
+        );
+        assert_eq!(
+            Board::try_setup(dimensions, &[corner, Coordinates::new(4, 4, dimensions)]),
+            Ok(setup_test_board())
+        );
+        assert_eq!(
+            Board::try_setup(dimensions, &[corner]),
+            Err(MoveError::InvalidPlayerCount(1))
+        );
+        assert_eq!(
+            Board::try_setup(dimensions, &[corner; 5]),
+            Err(MoveError::InvalidPlayerCount(5))
+        );
+    }
+
+    #[test]
+    fn test_try_make_move_errors() {
+        let board = setup_test_board();
+        let dimensions = board.dimensions();
+
+        assert_eq!(
+            board.try_make_move(Coordinates::new(2, 2, dimensions)),
+            Err(MoveError::EmptySquare(Coordinates::new(2, 2, dimensions)))
+        );
+        assert_eq!(
+            board.try_make_move(Coordinates::new(4, 4, dimensions)),
+            Err(MoveError::NotYourSquare {
+                coordinates: Coordinates::new(4, 4, dimensions),
+                owner: Player::Blue
+            })
+        );
+        assert!(matches!(
+            board.try_make_move(Coordinates::new(6, 0, Dimensions::new(8, 8))),
+            Err(MoveError::OutOfBounds {
+                row: 6,
+                column: 0,
+                ..
+            })
+        ));
+        assert_eq!(
+            board.try_make_move(Coordinates::new(0, 0, dimensions)),
+            Ok(board.make_move(Coordinates::new(0, 0, dimensions)))
+        );
+    }
+
+    #[test]
+    fn test_try_make_move_after_game_over() {
+        let mut board = Board::new(Dimensions::default(), 2, Player::Red);
+        board.set_square(
+            Coordinates::new(0, 0, board.dimensions()),
+            Square::occupied(Player::Red, 1),
+        );
+
+        assert_eq!(
+            board.try_make_move(Coordinates::new(0, 0, board.dimensions())),
+            Err(MoveError::GameAlreadyOver)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_make_move_invalid() {
+        let board = setup_test_board();
+        board.make_move(Coordinates::new(4, 4, board.dimensions()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_setup_outside_board() {
+        let small = Dimensions::new(3, 3);
+        let large = Dimensions::new(8, 8);
+        Board::setup(
+            small,
+            &[Coordinates::new(0, 0, small), Coordinates::new(7, 7, large)],
+        );
+    }
+
+    // Helper function to create a four player board with one player in each corner
+    fn setup_four_player_board() -> Board {
+        let dimensions = Dimensions::default();
+        Board::setup(
+            dimensions,
+            &[
+                Coordinates::new(0, 0, dimensions),
+                Coordinates::new(0, 4, dimensions),
+                Coordinates::new(4, 4, dimensions),
+                Coordinates::new(4, 0, dimensions),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_setup_four_players() {
+        let board = setup_four_player_board();
+        assert_eq!(board.players(), Player::ALL);
+        assert_eq!(board.turn(), Player::Red);
+        assert_eq!(board.grid[0][4], Square::occupied(Player::Blue, 3));
+        assert_eq!(board.grid[4][4], Square::occupied(Player::Green, 3));
+        assert_eq!(board.grid[4][0], Square::occupied(Player::Yellow, 3));
+        for player in Player::ALL {
+            assert_eq!(board.square_count(player), 1);
+        }
+    }
+
+    #[test]
+    fn test_turn_rotation() {
+        let mut board = setup_four_player_board();
+        for expected in [Player::Blue, Player::Green, Player::Yellow, Player::Red] {
+            board = board.make_move(board.get_valid_moves()[0]);
+            assert_eq!(board.turn(), expected);
+        }
+    }
+
+    #[test]
+    fn test_turn_rotation_skips_eliminated_players() {
+        let mut board = Board::new(Dimensions::default(), 3, Player::Red);
+        board.set_square(
+            Coordinates::new(0, 0, board.dimensions()),
+            Square::occupied(Player::Red, 3),
+        );
+        board.set_square(
+            Coordinates::new(0, 1, board.dimensions()),
+            Square::occupied(Player::Blue, 1),
+        );
+        board.set_square(
+            Coordinates::new(4, 4, board.dimensions()),
+            Square::occupied(Player::Green, 1),
+        );
+
+        // Red pops and captures Blue's only square, so Green moves next
+        let new_board = board.make_move(Coordinates::new(0, 0, board.dimensions()));
+        assert!(new_board.is_eliminated(Player::Blue));
+        assert!(!new_board.is_game_over());
+        assert_eq!(new_board.winner(), None);
+        assert_eq!(new_board.turn(), Player::Green);
+    }
+
+    #[test]
+    fn test_hash_key_is_updated_incrementally() {
+        let mut board = setup_test_board();
+        assert_eq!(board.hash_key(), compute_hash_key(&board));
+        for _ in 0..12 {
+            board = board.make_move(board.get_valid_moves()[0]);
+            assert_eq!(board.hash_key(), compute_hash_key(&board));
+        }
+
+        let board = setup_four_player_board();
+        let (new_board, _) = board.make_move_traced(board.get_valid_moves()[0]);
+        assert_eq!(new_board.hash_key(), compute_hash_key(&new_board));
+    }
+
+    #[test]
+    fn test_hash_key_identifies_positions() {
+        let board = setup_test_board();
+        let new_board = board.make_move(Coordinates::new(0, 0, board.dimensions()));
+        assert_ne!(board.hash_key(), new_board.hash_key());
+
+        // The same squares with another player to move is another position
+        let blue_to_move = Board {
+            turn: Player::Blue,
+            ..board.clone()
+        };
+        assert_ne!(compute_hash_key(&blue_to_move), board.hash_key());
+
+        // The same squares on a board of another size is another position
+        let wider = Board::new(Dimensions::new(5, 6), 2, Player::Red);
+        assert_ne!(
+            wider.hash_key(),
+            Board::new(Dimensions::default(), 2, Player::Red).hash_key()
+        );
+        assert_ne!(
+            Board::new(Dimensions::new(3, 4), 2, Player::Red),
+            Board::new(Dimensions::new(4, 3), 2, Player::Red)
+        );
+
+        // Reaching a position through a parsed notation gives the same key
+        let parsed: Board = new_board.to_notation().parse().unwrap();
+        assert_eq!(parsed.hash_key(), new_board.hash_key());
+    }
+
+    #[test]
+    fn test_packed_and_grid_representations_agree() {
+        for (dimensions, player_count) in [
+            (Dimensions::default(), 2),
+            (Dimensions::new(2, 2), 2),
+            (Dimensions::new(3, 7), 3),
+            (Dimensions::new(8, 8), 4),
+        ] {
+            let placements: Vec<Coordinates> = dimensions
+                .coordinates()
+                .step_by(dimensions.rows() * dimensions.columns() / player_count)
+                .take(player_count)
+                .collect();
+            let mut packed = Board::setup(dimensions, &placements);
+            let mut unpacked = packed.unpacked();
+
+            // Play a long game, picking moves with a simple deterministic generator
+            let mut seed = 17usize;
+            for _ in 0..200 {
+                if packed.is_game_over() {
+                    break;
+                }
+                let moves = packed.get_valid_moves();
+                assert_eq!(unpacked.get_valid_moves(), moves);
+
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let placement = moves[(seed >> 33) % moves.len()];
+                packed = packed.make_move(placement);
+                unpacked = unpacked.make_move(placement);
+
+                assert_eq!(packed, unpacked);
+                assert_eq!(packed.hash_key(), compute_hash_key(&packed));
+
+                // The packed copy matches one built from the grid
+                let mut bitboards = Bitboards::new(dimensions).unwrap();
+                for coordinates in dimensions.coordinates() {
+                    bitboards.set(
+                        bitboards.index(coordinates),
+                        packed.grid[coordinates.row()][coordinates.column()],
+                    );
+                }
+                assert_eq!(packed.bitboards, Some(bitboards));
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_boards_use_the_grid() {
+        let dimensions = Dimensions::new(12, 12);
+        let board = Board::setup(
+            dimensions,
+            &[
+                Coordinates::new(0, 0, dimensions),
+                Coordinates::new(11, 11, dimensions),
+            ],
+        );
+        assert!(board.bitboards.is_none());
+        assert_eq!(
+            board.valid_moves().collect::<Vec<_>>(),
+            [Coordinates::new(0, 0, dimensions)]
+        );
+
+        let new_board = board.make_move(Coordinates::new(0, 0, dimensions));
+        assert_eq!(new_board.square_count(Player::Red), 2);
+        assert_eq!(
+            new_board.get_valid_moves(),
+            [Coordinates::new(11, 11, dimensions)]
+        );
+    }
+
+    #[test]
+    fn test_winner() {
+        let mut board = Board::new(Dimensions::default(), 3, Player::Red);
+        board.set_square(
+            Coordinates::new(0, 0, board.dimensions()),
+            Square::occupied(Player::Red, 3),
+        );
+        board.set_square(
+            Coordinates::new(0, 1, board.dimensions()),
+            Square::occupied(Player::Blue, 1),
+        );
+        board.set_square(
+            Coordinates::new(1, 0, board.dimensions()),
+            Square::occupied(Player::Green, 2),
+        );
+
+        let new_board = board.make_move(Coordinates::new(0, 0, board.dimensions()));
+        assert!(new_board.is_game_over());
+        assert_eq!(new_board.winner(), Some(Player::Red));
+        assert_eq!(new_board.turn(), Player::Red);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_turn_not_in_game() {
+        Board::new(Dimensions::default(), 2, Player::Green);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let dimensions = Dimensions::new(2, 2);
+        let board = Board::setup(
+            dimensions,
+            &[
+                Coordinates::new(0, 0, dimensions),
+                Coordinates::new(1, 1, dimensions),
+            ],
+        );
+        let json = serde_json::to_string(&board).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"dimensions":{"rows":2,"columns":2},"players":2,"turn":"Red","grid":[[{"owner":"Red","value":3},null],[null,{"owner":"Blue","value":3}]]}"#
+        );
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_invalid_boards() {
+        let board = |players: usize, turn: &str, square: &str| {
+            format!(
+                r#"{{"dimensions":{{"rows":2,"columns":2}},"players":{players},"turn":"{turn}","grid":[[{square},null],[null,null]]}}"#
+            )
+        };
+        let red = r#"{"owner":"Red","value":3}"#;
+
+        assert!(serde_json::from_str::<Board>(&board(2, "Red", red)).is_ok());
+        assert!(serde_json::from_str::<Board>(&board(5, "Red", red)).is_err());
+        assert!(serde_json::from_str::<Board>(&board(2, "Green", red)).is_err());
+        assert!(
+            serde_json::from_str::<Board>(&board(2, "Red", r#"{"owner":"Red","value":4}"#))
+                .is_err()
+        );
+        assert!(
+            serde_json::from_str::<Board>(&board(2, "Red", r#"{"owner":"Yellow","value":1}"#))
+                .is_err()
+        );
+        // Blue cannot be to move without a square
+        assert!(serde_json::from_str::<Board>(&board(2, "Blue", red)).is_err());
+    }
 }
@@ -1,29 +1,85 @@
 //! Defines the `Coordinates` type for locating squares on the game board.
-use crate::{BOARD_COLUMN_SIZE, BOARD_ROW_SIZE};
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::{dimensions::Dimensions, move_error::MoveError};
 
 /// Points to a specific square on the board using row and column indices.
 ///
-/// This struct guarantees that its coordinates are always within the bounds
-/// of the board, as defined by `BOARD_ROW_SIZE` and `BOARD_COLUMN_SIZE`.
+/// This struct guarantees that its coordinates are within the bounds of the
+/// board `Dimensions` they were created for.
+///
+/// Coordinates are written in algebraic notation: a column letter followed by a
+/// one-based row number, so `a1` is the top-left square and `c4` is row `3`,
+/// column `2`. Columns past `z` continue as `aa`, `ab` and so on.
+///
+/// Coordinates are serialized in the same notation. Like parsed ones, deserialized
+/// coordinates do not know their board, so the board, game or record holding them
+/// checks they lie on it.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "CoordinatesFields", into = "CoordinatesFields")
+)]
 pub struct Coordinates {
     row: usize,
     column: usize,
 }
 
 impl Coordinates {
-    /// Creates a new `Coordinates` instance.
+    /// Creates a new `Coordinates` instance on a board of the given `dimensions`.
     /// row and column are always zero-based indexes.
     ///
     /// # Panics
     ///
     /// This function will panic if `row` or `column` are outside the board dimensions.
-    pub fn new(row: usize, column: usize) -> Self {
-        assert!(row < BOARD_ROW_SIZE);
-        assert!(column < BOARD_COLUMN_SIZE);
+    pub fn new(row: usize, column: usize, dimensions: Dimensions) -> Self {
+        assert!(row < dimensions.rows());
+        assert!(column < dimensions.columns());
         Self { row, column }
     }
 
+    /// Creates a new `Coordinates` instance, without panicking on out-of-range input.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MoveError::OutOfBounds` if `row` or `column` are outside the board dimensions.
+    pub fn try_new(row: usize, column: usize, dimensions: Dimensions) -> Result<Self, MoveError> {
+        if row < dimensions.rows() && column < dimensions.columns() {
+            Ok(Self { row, column })
+        } else {
+            Err(MoveError::OutOfBounds {
+                row,
+                column,
+                dimensions,
+            })
+        }
+    }
+
+    /// Creates coordinates without checking them against a board, for squares read
+    /// back from a packed form of coordinates that were checked when created.
+    pub(crate) fn from_indexes(row: usize, column: usize) -> Self {
+        Self { row, column }
+    }
+
+    /// Parses a square written in algebraic notation, e.g. `c4`, checking it lies on
+    /// a board of the given `dimensions`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParseCoordinatesError::Invalid` if `text` is not algebraic notation,
+    /// or `ParseCoordinatesError::OutOfBounds` if the square is outside the board.
+    pub fn parse(text: &str, dimensions: Dimensions) -> Result<Self, ParseCoordinatesError> {
+        let coordinates = text.parse::<Coordinates>()?;
+        if !dimensions.contains(coordinates) {
+            return Err(ParseCoordinatesError::OutOfBounds {
+                text: text.to_string(),
+                dimensions,
+            });
+        }
+        Ok(coordinates)
+    }
+
     /// Returns the zero-based row index.
     pub fn row(&self) -> usize {
         self.row
@@ -34,28 +90,140 @@ impl Coordinates {
         self.column
     }
 
-    /// Returns a list of valid orthogonally adyacent neighbor coordinates.
-    pub fn neighbors(&self) -> Vec<Coordinates> {
-        let mut neighbors = Vec::with_capacity(4);
+    /// Returns the valid orthogonally adyacent neighbor coordinates on a board of
+    /// the given `dimensions`, in north, south, west, east order.
+    ///
+    /// The neighbors are produced without allocating, which matters because every
+    /// pop visits them.
+    pub fn neighbors(&self, dimensions: Dimensions) -> impl Iterator<Item = Coordinates> + use<> {
+        let Coordinates { row, column } = *self;
+        [
+            // North
+            (row > 0).then(|| Coordinates::new(row - 1, column, dimensions)),
+            // South
+            (row < dimensions.rows() - 1).then(|| Coordinates::new(row + 1, column, dimensions)),
+            // West
+            (column > 0).then(|| Coordinates::new(row, column - 1, dimensions)),
+            // East
+            (column < dimensions.columns() - 1)
+                .then(|| Coordinates::new(row, column + 1, dimensions)),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+// Writes the column letters of a zero-based column index: `a` to `z`, then `aa`, `ab`...
+fn column_name(column: usize) -> String {
+    let mut name = Vec::new();
+    let mut remaining = column + 1;
+    while remaining > 0 {
+        remaining -= 1;
+        name.push(b'a' + (remaining % 26) as u8);
+        remaining /= 26;
+    }
+    name.reverse();
+    String::from_utf8(name).expect("column names are ASCII")
+}
 
-        // Check North
-        if self.row > 0 {
-            neighbors.push(Coordinates::new(self.row - 1, self.column));
-        }
-        // Check South
-        if self.row < (BOARD_ROW_SIZE - 1) {
-            neighbors.push(Coordinates::new(self.row + 1, self.column));
+// Writes a square in algebraic notation, even when it does not fit in `Coordinates`.
+pub(crate) fn algebraic_name(row: usize, column: usize) -> String {
+    format!("{}{}", column_name(column), row + 1)
+}
+
+/// Formats the coordinates in algebraic notation, e.g. `c4`.
+impl fmt::Display for Coordinates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", algebraic_name(self.row, self.column))
+    }
+}
+
+/// The reasons a square written in algebraic notation can fail to parse.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParseCoordinatesError {
+    /// The text is not column letters followed by a row number from 1.
+    Invalid(String),
+    /// The square does not lie on the board.
+    OutOfBounds {
+        text: String,
+        dimensions: Dimensions,
+    },
+}
+
+/// A way to print to terminal why the square could not be parsed
+impl fmt::Display for ParseCoordinatesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseCoordinatesError::Invalid(text) => {
+                write!(f, "`{text}` is not a square, expected e.g. `c4`")
+            }
+            ParseCoordinatesError::OutOfBounds { text, dimensions } => {
+                write!(f, "{text} is outside the {dimensions} board")
+            }
         }
-        // Check West
-        if self.column > 0 {
-            neighbors.push(Coordinates::new(self.row, self.column - 1));
+    }
+}
+
+impl Error for ParseCoordinatesError {}
+
+/// Parses a square written in algebraic notation, e.g. `c4`.
+///
+/// The text alone does not say which board the square belongs to, so the result is
+/// only checked when it is used, e.g. by [`Board::try_make_move`](crate::board::Board::try_make_move).
+/// Use [`Coordinates::parse`] to check it against a board size right away.
+impl FromStr for Coordinates {
+    type Err = ParseCoordinatesError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseCoordinatesError::Invalid(text.to_string());
+
+        let split = text
+            .find(|character: char| !character.is_ascii_alphabetic())
+            .ok_or_else(invalid)?;
+        let (letters, digits) = text.split_at(split);
+        if letters.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+            return Err(invalid());
         }
-        // Check East
-        if self.column < (BOARD_COLUMN_SIZE - 1) {
-            neighbors.push(Coordinates::new(self.row, self.column + 1));
+
+        let column = letters
+            .bytes()
+            .try_fold(0usize, |column, letter| {
+                let value = (letter.to_ascii_lowercase() - b'a') as usize + 1;
+                column.checked_mul(26)?.checked_add(value)
+            })
+            .ok_or_else(invalid)?;
+        let row = digits.parse::<usize>().map_err(|_| invalid())?;
+        if row == 0 {
+            return Err(invalid());
         }
 
-        neighbors
+        Ok(Self {
+            row: row - 1,
+            column: column - 1,
+        })
+    }
+}
+
+// The serialized form of `Coordinates`, their algebraic notation, checked before it
+// becomes them.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CoordinatesFields(String);
+
+#[cfg(feature = "serde")]
+impl TryFrom<CoordinatesFields> for Coordinates {
+    type Error = ParseCoordinatesError;
+
+    fn try_from(fields: CoordinatesFields) -> Result<Self, Self::Error> {
+        fields.0.parse()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Coordinates> for CoordinatesFields {
+    fn from(coordinates: Coordinates) -> Self {
+        CoordinatesFields(coordinates.to_string())
     }
 }
 
@@ -63,23 +231,29 @@ impl Coordinates {
 mod tests {
     use super::*;
 
+    const DIMENSIONS: [(usize, usize); 4] = [(3, 3), (4, 6), (8, 8), (10, 10)];
+
     #[test]
     fn new_coordinates_valid() {
-        for row in 0..BOARD_ROW_SIZE {
-            for column in 0..BOARD_COLUMN_SIZE {
-                let coordinates = Coordinates::new(row, column);
+        for (rows, columns) in DIMENSIONS {
+            let dimensions = Dimensions::new(rows, columns);
+            for row in 0..rows {
+                for column in 0..columns {
+                    let coordinates = Coordinates::new(row, column, dimensions);
 
-                assert_eq!(coordinates.row, row);
-                assert_eq!(coordinates.column, column);
+                    assert_eq!(coordinates.row, row);
+                    assert_eq!(coordinates.column, column);
+                }
             }
         }
     }
 
     #[test]
     fn coordinates_getters() {
-        for row in 0..BOARD_ROW_SIZE {
-            for column in 0..BOARD_COLUMN_SIZE {
-                let coordinates = Coordinates::new(row, column);
+        let dimensions = Dimensions::default();
+        for row in 0..dimensions.rows() {
+            for column in 0..dimensions.columns() {
+                let coordinates = Coordinates::new(row, column, dimensions);
 
                 assert_eq!(coordinates.row(), row);
                 assert_eq!(coordinates.column(), column);
@@ -91,19 +265,120 @@ mod tests {
     #[should_panic]
     fn new_coordinates_invalid_row() {
         // This should panic because the row is out of bounds.
-        Coordinates::new(BOARD_ROW_SIZE, 0);
+        Coordinates::new(4, 0, Dimensions::new(4, 6));
     }
 
     #[test]
     #[should_panic]
     fn new_coordinates_invalid_column() {
         // This should panic because the column is out of bounds.
-        Coordinates::new(0, BOARD_COLUMN_SIZE);
+        Coordinates::new(0, 6, Dimensions::new(4, 6));
     }
 
     #[test]
     #[should_panic]
     fn invalid_coordinates() {
-        Coordinates::new(BOARD_ROW_SIZE, BOARD_COLUMN_SIZE);
+        let dimensions = Dimensions::default();
+        Coordinates::new(dimensions.rows(), dimensions.columns(), dimensions);
+    }
+
+    #[test]
+    fn try_new_coordinates() {
+        let dimensions = Dimensions::new(4, 6);
+        assert_eq!(
+            Coordinates::try_new(3, 5, dimensions),
+            Ok(Coordinates::new(3, 5, dimensions))
+        );
+        assert_eq!(
+            Coordinates::try_new(4, 0, dimensions),
+            Err(MoveError::OutOfBounds {
+                row: 4,
+                column: 0,
+                dimensions
+            })
+        );
+        assert!(Coordinates::try_new(0, 6, dimensions).is_err());
+    }
+
+    #[test]
+    fn neighbors_respect_board_edges() {
+        let dimensions = Dimensions::new(3, 4);
+        let neighbor_count = |row, column| {
+            Coordinates::new(row, column, dimensions)
+                .neighbors(dimensions)
+                .count()
+        };
+
+        assert_eq!(neighbor_count(0, 0), 2);
+        assert_eq!(neighbor_count(0, 1), 3);
+        assert_eq!(neighbor_count(1, 1), 4);
+        assert_eq!(neighbor_count(2, 3), 2);
+    }
+
+    #[test]
+    fn display_trait() {
+        let dimensions = Dimensions::new(10, 30);
+        assert_eq!(Coordinates::new(0, 0, dimensions).to_string(), "a1");
+        assert_eq!(Coordinates::new(3, 2, dimensions).to_string(), "c4");
+        assert_eq!(Coordinates::new(9, 25, dimensions).to_string(), "z10");
+        assert_eq!(Coordinates::new(0, 26, dimensions).to_string(), "aa1");
+        assert_eq!(Coordinates::new(1, 29, dimensions).to_string(), "ad2");
+    }
+
+    #[test]
+    fn from_str() {
+        let dimensions = Dimensions::new(10, 30);
+        assert_eq!("c4".parse(), Ok(Coordinates::new(3, 2, dimensions)));
+        assert_eq!("C4".parse(), Ok(Coordinates::new(3, 2, dimensions)));
+        assert_eq!("aa1".parse(), Ok(Coordinates::new(0, 26, dimensions)));
+        assert_eq!("j10".parse(), Ok(Coordinates::new(9, 9, dimensions)));
+
+        for text in ["", "c", "4", "c0", "4c", "c4d", "c-4", "é4"] {
+            assert_eq!(
+                text.parse::<Coordinates>(),
+                Err(ParseCoordinatesError::Invalid(text.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let dimensions = Dimensions::new(8, 28);
+        for coordinates in dimensions.coordinates() {
+            assert_eq!(coordinates.to_string().parse(), Ok(coordinates));
+        }
+    }
+
+    #[test]
+    fn parse_respects_board_size() {
+        let dimensions = Dimensions::new(4, 6);
+        assert_eq!(
+            Coordinates::parse("f4", dimensions),
+            Ok(Coordinates::new(3, 5, dimensions))
+        );
+        assert_eq!(
+            Coordinates::parse("g1", dimensions),
+            Err(ParseCoordinatesError::OutOfBounds {
+                text: String::from("g1"),
+                dimensions
+            })
+        );
+        assert!(Coordinates::parse("a5", dimensions).is_err());
+        assert!(Coordinates::parse("5a", dimensions).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let coordinates = Coordinates::new(3, 2, Dimensions::default());
+        let json = serde_json::to_string(&coordinates).unwrap();
+
+        assert_eq!(json, r#""c4""#);
+        assert_eq!(
+            serde_json::from_str::<Coordinates>(&json).unwrap(),
+            coordinates
+        );
+        assert!(serde_json::from_str::<Coordinates>(r#""c0""#).is_err());
+        assert!(serde_json::from_str::<Coordinates>(r#"{"row":3,"column":2}"#).is_err());
     }
 }
@@ -0,0 +1,214 @@
+//! Defines the `Dimensions` type, which describes the size of a game board.
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::coordinates::Coordinates;
+
+/// The number of rows and columns of a game board.
+///
+/// Boards are sized at runtime, so a single build of the crate can play on any
+/// board. Both dimensions must be 2 or greater.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "DimensionsFields"))]
+pub struct Dimensions {
+    rows: usize,
+    columns: usize,
+}
+
+impl Dimensions {
+    /// The smallest number of rows or columns a board can have.
+    pub const MIN_SIZE: usize = 2;
+
+    /// Creates a new `Dimensions` instance.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `rows` or `columns` are smaller than `MIN_SIZE`.
+    pub fn new(rows: usize, columns: usize) -> Self {
+        assert!(rows >= Self::MIN_SIZE);
+        assert!(columns >= Self::MIN_SIZE);
+        Self { rows, columns }
+    }
+
+    /// Returns the number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the number of columns.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Returns the total number of squares on a board of this size.
+    pub fn area(&self) -> usize {
+        self.rows * self.columns
+    }
+
+    /// Checks whether the given coordinates lie on a board of this size.
+    pub fn contains(&self, coordinates: Coordinates) -> bool {
+        coordinates.row() < self.rows && coordinates.column() < self.columns
+    }
+
+    /// Returns an iterator over every square of the board, in row-major order.
+    pub fn coordinates(&self) -> impl Iterator<Item = Coordinates> + use<> {
+        let dimensions = *self;
+        (0..self.rows).flat_map(move |row| {
+            (0..dimensions.columns).map(move |column| Coordinates::new(row, column, dimensions))
+        })
+    }
+}
+
+/// The standard `5x5` board described in the rules.
+impl Default for Dimensions {
+    fn default() -> Self {
+        Self::new(5, 5)
+    }
+}
+
+/// Formats the dimensions as `ROWSxCOLUMNS`, e.g. `5x5`.
+impl fmt::Display for Dimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.rows, self.columns)
+    }
+}
+
+/// The error returned when a `ROWSxCOLUMNS` string does not describe a valid board size.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseDimensionsError(String);
+
+/// A way to print to terminal why the dimensions could not be parsed
+impl fmt::Display for ParseDimensionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid board size `{}`, expected ROWSxCOLUMNS with both at least {}",
+            self.0,
+            Dimensions::MIN_SIZE
+        )
+    }
+}
+
+impl Error for ParseDimensionsError {}
+
+/// Parses dimensions written as `ROWSxCOLUMNS`, e.g. `5x5`.
+impl FromStr for Dimensions {
+    type Err = ParseDimensionsError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ParseDimensionsError(text.to_string());
+
+        let (rows, columns) = text.trim().split_once(['x', 'X']).ok_or_else(error)?;
+        let rows = rows.parse::<usize>().map_err(|_| error())?;
+        let columns = columns.parse::<usize>().map_err(|_| error())?;
+
+        if rows < Self::MIN_SIZE || columns < Self::MIN_SIZE {
+            return Err(error());
+        }
+        Ok(Self::new(rows, columns))
+    }
+}
+
+// The serialized form of `Dimensions`, checked before it becomes one.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct DimensionsFields {
+    rows: usize,
+    columns: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<DimensionsFields> for Dimensions {
+    type Error = ParseDimensionsError;
+
+    fn try_from(fields: DimensionsFields) -> Result<Self, Self::Error> {
+        if fields.rows < Self::MIN_SIZE || fields.columns < Self::MIN_SIZE {
+            return Err(ParseDimensionsError(format!(
+                "{}x{}",
+                fields.rows, fields.columns
+            )));
+        }
+        Ok(Self::new(fields.rows, fields.columns))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_dimensions_valid() {
+        let dimensions = Dimensions::new(4, 6);
+        assert_eq!(dimensions.rows(), 4);
+        assert_eq!(dimensions.columns(), 6);
+        assert_eq!(dimensions.area(), 24);
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_dimensions_invalid_rows() {
+        Dimensions::new(1, 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_dimensions_invalid_columns() {
+        Dimensions::new(5, 1);
+    }
+
+    #[test]
+    fn default_is_standard_board() {
+        assert_eq!(Dimensions::default(), Dimensions::new(5, 5));
+    }
+
+    #[test]
+    fn contains() {
+        let dimensions = Dimensions::new(3, 4);
+        let larger = Dimensions::new(10, 10);
+        assert!(dimensions.contains(Coordinates::new(2, 3, larger)));
+        assert!(!dimensions.contains(Coordinates::new(3, 0, larger)));
+        assert!(!dimensions.contains(Coordinates::new(0, 4, larger)));
+    }
+
+    #[test]
+    fn coordinates_iterates_row_major() {
+        let dimensions = Dimensions::new(2, 3);
+        let all: Vec<_> = dimensions.coordinates().collect();
+        assert_eq!(all.len(), 6);
+        assert_eq!(all[0], Coordinates::new(0, 0, dimensions));
+        assert_eq!(all[2], Coordinates::new(0, 2, dimensions));
+        assert_eq!(all[3], Coordinates::new(1, 0, dimensions));
+    }
+
+    #[test]
+    fn display_trait() {
+        assert_eq!(Dimensions::new(4, 6).to_string(), "4x6");
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("4x6".parse(), Ok(Dimensions::new(4, 6)));
+        assert_eq!("10X10".parse(), Ok(Dimensions::new(10, 10)));
+        assert_eq!(
+            "1x5".parse::<Dimensions>(),
+            Err(ParseDimensionsError(String::from("1x5")))
+        );
+        assert!("5".parse::<Dimensions>().is_err());
+        assert!("ax5".parse::<Dimensions>().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let dimensions = Dimensions::new(4, 6);
+        let json = serde_json::to_string(&dimensions).unwrap();
+
+        assert_eq!(json, r#"{"rows":4,"columns":6}"#);
+        assert_eq!(
+            serde_json::from_str::<Dimensions>(&json).unwrap(),
+            dimensions
+        );
+        assert!(serde_json::from_str::<Dimensions>(r#"{"rows":1,"columns":6}"#).is_err());
+    }
+}
@@ -1,230 +1,1477 @@
-//! The game's AI engine, powered by a minimax search algorithm with alpha-beta pruning.
+//! The game's AI engine, powered by a negamax search with alpha-beta pruning,
+//! principal variation search and aspiration windows.
 use std::{
-    collections::{HashMap, HashSet},
-    time::Duration,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
 use crate::{
-    BOARD_COLUMN_SIZE, BOARD_ROW_SIZE, board::Board, player::Player, prelude::Coordinates,
+    board::Board,
+    dimensions::Dimensions,
+    evaluator::{Evaluator, StandardEvaluator},
+    player::Player,
+    prelude::Coordinates,
+    score::Score,
+    transposition_table::{Bound, TranspositionTable},
 };
 
-/// Evaluates the board state from a static, Red-player perspective.
+/// The state shared by every node of a search.
+struct SearchContext<'a, E: Evaluator> {
+    transposition_table: &'a TranspositionTable,
+    evaluator: &'a E,
+    nodes: u64,
+    max_nodes: Option<u64>,
+    // When the time limit runs out, checked every `TIME_CHECK_NODES` nodes
+    deadline: Option<Instant>,
+    stop: &'a StopHandle,
+    aborted: bool,
+    // The best line found from the node at each ply, reused from node to node
+    lines: Vec<Vec<Coordinates>>,
+    // The best move of the last completed iteration, searched first at the root
+    root_move: Option<Coordinates>,
+    // The moves of the node at each ply with their priority, reused from node to node
+    move_lists: Vec<Vec<(u64, Coordinates)>>,
+    // The last two moves that caused a cutoff at each ply
+    killers: Vec<[Option<Coordinates>; 2]>,
+    // How many cutoffs each player's moves caused, weighted by depth, per square
+    history: Vec<u64>,
+    dimensions: Dimensions,
+    // The explosive moves the current quiescence search may still expand
+    quiescence_budget: u64,
+}
+
+impl<'a, E: Evaluator> SearchContext<'a, E> {
+    /// Creates the context of a search of `board`, which started at `start`.
+    fn new(
+        board: &Board,
+        limits: SearchLimits,
+        start: Instant,
+        stop: &'a StopHandle,
+        transposition_table: &'a TranspositionTable,
+        evaluator: &'a E,
+    ) -> Self {
+        Self {
+            transposition_table,
+            evaluator,
+            nodes: 0,
+            max_nodes: limits.max_nodes(),
+            deadline: limits.max_time().map(|time| start + time),
+            stop,
+            aborted: false,
+            lines: Vec::new(),
+            root_move: None,
+            move_lists: Vec::new(),
+            killers: Vec::new(),
+            history: vec![0; Player::MAX_PLAYERS * board.dimensions().area()],
+            dimensions: board.dimensions(),
+            quiescence_budget: 0,
+        }
+    }
+
+    /// Returns the best line of the node at `ply`, empty until a move is scored.
+    fn line(&mut self, ply: usize) -> &mut Vec<Coordinates> {
+        if self.lines.len() <= ply {
+            self.lines.resize_with(ply + 1, Vec::new);
+        }
+        &mut self.lines[ply]
+    }
+
+    /// Makes `best_move` followed by the line of the node after it the best line at `ply`.
+    fn set_line(&mut self, ply: usize, best_move: Coordinates) {
+        self.line(ply + 1);
+        let (lines, child_lines) = self.lines.split_at_mut(ply + 1);
+        lines[ply].clear();
+        lines[ply].push(best_move);
+        lines[ply].extend_from_slice(&child_lines[0]);
+    }
+
+    /// Returns the moves of `board`, the most promising first, in the list of the
+    /// node at `ply`. The list is taken out of the context and should be given back
+    /// with `restore_moves`.
+    ///
+    /// The hash move comes first, then the killer moves of the ply and then the
+    /// other moves by their history.
+    fn order_moves(
+        &mut self,
+        board: &Board,
+        ply: usize,
+        hash_move: Option<Coordinates>,
+    ) -> Vec<(u64, Coordinates)> {
+        if self.move_lists.len() <= ply {
+            self.move_lists.resize_with(ply + 1, Vec::new);
+        }
+        let mut moves = std::mem::take(&mut self.move_lists[ply]);
+        moves.clear();
+
+        let player = board.turn();
+        let killers = self.killers.get(ply).copied().unwrap_or_default();
+        moves.extend(board.valid_moves().map(|current_move| {
+            let priority = if Some(current_move) == hash_move {
+                u64::MAX
+            } else if Some(current_move) == killers[0] {
+                u64::MAX - 1
+            } else if Some(current_move) == killers[1] {
+                u64::MAX - 2
+            } else {
+                self.history[self.history_index(player, current_move)]
+            };
+            (priority, current_move)
+        }));
+        // Ties keep the row-major order, so searches are reproducible
+        moves.sort_unstable_by_key(|(priority, current_move)| {
+            (
+                std::cmp::Reverse(*priority),
+                current_move.row(),
+                current_move.column(),
+            )
+        });
+        moves
+    }
+
+    /// Gives back the list taken by `order_moves`, so its memory is reused.
+    fn restore_moves(&mut self, ply: usize, moves: Vec<(u64, Coordinates)>) {
+        self.move_lists[ply] = moves;
+    }
+
+    /// Remembers that `player` playing `current_move` caused a cutoff at `ply`, in a
+    /// search `depth` plies deep.
+    fn record_cutoff(
+        &mut self,
+        player: Player,
+        ply: usize,
+        depth: usize,
+        current_move: Coordinates,
+    ) {
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(current_move) {
+            killers[1] = killers[0];
+            killers[0] = Some(current_move);
+        }
+
+        // Deeper cutoffs save more work
+        let index = self.history_index(player, current_move);
+        self.history[index] += (depth * depth) as u64;
+    }
+
+    // The position of `player` playing `current_move` in the history.
+    fn history_index(&self, player: Player, current_move: Coordinates) -> usize {
+        player.index() * self.dimensions.area()
+            + current_move.row() * self.dimensions.columns()
+            + current_move.column()
+    }
+
+    /// Records a visit to a node and returns whether the search must stop.
+    fn visit(&mut self) -> bool {
+        if self
+            .max_nodes
+            .is_some_and(|max_nodes| self.nodes >= max_nodes)
+            || self.stop.is_stopped()
+            || self.is_past_deadline()
+        {
+            self.aborted = true;
+        } else {
+            self.nodes += 1;
+        }
+        self.aborted
+    }
+
+    // Reading the clock is slow next to visiting a node, so it is only read every
+    // `TIME_CHECK_NODES` nodes.
+    fn is_past_deadline(&self) -> bool {
+        self.nodes > 0
+            && self.nodes.is_multiple_of(TIME_CHECK_NODES)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// The limits that end a search.
 ///
-/// A positive score is favorable for Red, while a negative score is favorable for Blue.
-/// The magnitude of the score represents the degree of advantage. A win for either
-/// player is represented by `i32::MAX` or `i32::MIN`.
+/// Limits can be combined, the search ends as soon as any of them is reached.
+/// Without any limit the search is infinite and runs until it is stopped, see
+/// `search_until_stopped`.
 ///
-/// # Returns
-/// An `i32` score representing the board state.
-pub fn evaluate(board: &Board) -> i32 {
-    let mut score = 0;
-    let mut red_squares = 0usize;
-    let mut blue_squares = 0usize;
-
-    let mut visited_squares = HashSet::new();
-
-    for r in 0..BOARD_ROW_SIZE {
-        for c in 0..BOARD_COLUMN_SIZE {
-            if let Some(owner) = board.grid()[r][c].owner() {
-                match owner {
-                    Player::Red => {
-                        score += 1;
-                        red_squares += 1;
-                    }
-                    Player::Blue => {
-                        score -= 1;
-                        blue_squares += 1;
-                    }
-                }
-                let our_value = board.grid()[r][c].value();
-                for neighbor in Coordinates::new(r, c).neighbors() {
-                    if visited_squares.contains(&neighbor)
-                        || board.grid()[neighbor.row()][neighbor.column()]
-                            .owner()
-                            .is_none()
-                    {
-                        continue;
-                    }
-
-                    let perimeter_value = if our_value
-                        > board.grid()[neighbor.row()][neighbor.column()].value()
-                    {
-                        10
-                    } else if our_value == board.grid()[neighbor.row()][neighbor.column()].value() {
-                        if owner == board.turn() { 10 } else { -10 }
-                    } else {
-                        0
-                    };
-
-                    if board.turn() == Player::Red {
-                        score += perimeter_value;
-                    } else {
-                        score -= perimeter_value;
-                    }
-                    visited_squares.insert(neighbor);
-                }
-            }
+/// The limits also set how many threads run the search, one by default.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use color_wars::prelude::*;
+///
+/// // Search 4 plies deep, or for at most a second
+/// let limits = SearchLimits::depth(4).with_time(Duration::from_secs(1));
+/// assert_eq!(limits.max_depth(), Some(4));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchLimits {
+    depth: Option<usize>,
+    nodes: Option<u64>,
+    time: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    threads: Option<usize>,
+}
+
+impl SearchLimits {
+    /// Creates limits that never end the search on their own.
+    pub fn infinite() -> Self {
+        Self::default()
+    }
+
+    /// Creates limits that stop after completing a search `depth` plies deep.
+    pub fn depth(depth: usize) -> Self {
+        Self::default().with_depth(depth)
+    }
+
+    /// Creates limits that stop after visiting `nodes` positions.
+    pub fn nodes(nodes: u64) -> Self {
+        Self::default().with_nodes(nodes)
+    }
+
+    /// Creates limits that stop the search once `time` has passed.
+    ///
+    /// The search checks the clock every thousand or so positions, so it can run a
+    /// little over `time`. It then returns the result of the last completed depth.
+    pub fn time(time: Duration) -> Self {
+        Self::default().with_time(time)
+    }
+
+    /// Also stops after completing a search `depth` plies deep.
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Also stops after visiting `nodes` positions.
+    pub fn with_nodes(mut self, nodes: u64) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    /// Also stops once `time` has passed, see `SearchLimits::time`.
+    pub fn with_time(mut self, time: Duration) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Runs the search on `threads` threads.
+    ///
+    /// The threads search the same position and share the transposition table,
+    /// each one finding results the others reuse. The result is the one of the
+    /// main thread, with the positions visited by every thread. The node limit
+    /// counts the positions visited by the main thread.
+    ///
+    /// A search on a single thread always gives the same result for the same
+    /// limits, which is not the case with several threads.
+    ///
+    /// # Panics
+    /// Panics if `threads` is `0`.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "a search needs at least one thread");
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Returns the deepest search allowed, if limited.
+    pub fn max_depth(&self) -> Option<usize> {
+        self.depth
+    }
+
+    /// Returns the number of positions that can be visited, if limited.
+    pub fn max_nodes(&self) -> Option<u64> {
+        self.nodes
+    }
+
+    /// Returns the time the search can use, if limited.
+    pub fn max_time(&self) -> Option<Duration> {
+        self.time
+    }
+
+    /// Returns the number of threads that run the search.
+    pub fn threads(&self) -> usize {
+        self.threads.unwrap_or(1)
+    }
+
+    /// Returns whether no limit is set.
+    pub fn is_infinite(&self) -> bool {
+        self.depth.is_none() && self.nodes.is_none() && self.time.is_none()
+    }
+}
+
+/// The outcome of a call to `search`.
+///
+/// The score is from Red's perspective, so a positive score
+/// favors Red and a negative one favors its opponents. Wins and losses are counted
+/// in plies from the searched position.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchResult {
+    best_move: Coordinates,
+    score: Score,
+    depth: usize,
+    nodes: u64,
+    elapsed: Duration,
+    principal_variation: Vec<Coordinates>,
+    proven_win: bool,
+    proven_loss: bool,
+}
+
+impl SearchResult {
+    // Creates the result of a search completed `depth` plies deep on `board`,
+    // without any statistics yet.
+    fn new(
+        board: &Board,
+        best_move: Coordinates,
+        score: Score,
+        depth: usize,
+        principal_variation: Vec<Coordinates>,
+    ) -> Self {
+        // Red is scored against all of its opponents combined, so a loss for Red
+        // only singles out the winner when there is one opponent.
+        let red_wins = depth > 0 && score.is_win();
+        let red_loses = depth > 0 && score.is_loss();
+        let (proven_win, proven_loss) = if board.turn() == Player::Red {
+            (red_wins, red_loses)
+        } else {
+            (red_loses && board.players().len() == 2, red_wins)
+        };
+
+        Self {
+            best_move,
+            score,
+            depth,
+            nodes: 0,
+            elapsed: Duration::ZERO,
+            principal_variation,
+            proven_win,
+            proven_loss,
         }
     }
 
-    // P1 wins
-    if blue_squares == 0 && red_squares > 0 {
-        score = i32::MAX;
+    /// Returns the move the engine recommends.
+    pub fn best_move(&self) -> Coordinates {
+        self.best_move
+    }
+
+    /// Returns the evaluation of the best move, from Red's perspective.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+
+    /// Returns the deepest search that was completed, `0` if none was.
+    pub fn depth(&self) -> usize {
+        self.depth
     }
 
-    // P2 wins
-    if red_squares == 0 && blue_squares > 0 {
-        score = i32::MIN;
+    /// Returns the number of positions visited during the search.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
     }
 
-    score
+    /// Returns how long the search took.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the line of play the engine expects, starting with the best move.
+    pub fn principal_variation(&self) -> &[Coordinates] {
+        &self.principal_variation
+    }
+
+    /// Returns whether the player to move can force a win.
+    ///
+    /// In games with more than two players Red's opponents are scored together,
+    /// so only a win for Red can be proven.
+    pub fn is_proven_win(&self) -> bool {
+        self.proven_win
+    }
+
+    /// Returns whether the player to move loses against best play.
+    pub fn is_proven_loss(&self) -> bool {
+        self.proven_loss
+    }
 }
 
-/// K = board, V = (score, depth)
-type TranspositionTable = HashMap<Board, (i32, usize)>;
+/// A shared flag that stops a running search.
+///
+/// Clones share the same flag, so one clone can be handed to the thread running
+/// the search while another stops it, for example when the user makes a move.
+#[derive(Debug, Clone, Default)]
+pub struct StopHandle(Arc<AtomicBool>);
+
+impl StopHandle {
+    /// Creates a handle that is not stopped.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every search using this handle to stop as soon as possible.
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether the searches using this handle were asked to stop.
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clears the stop request so the handle can be used for another search.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
 
 /// The main entry point for the AI search.
 ///
-/// It finds the best move for the player whose turn it is on the given `board`.
+/// It finds the best move for the player whose turn it is on the given `board`,
+/// searching one ply deeper at a time until one of the `limits` is reached.
+/// Only fully searched depths count, so a depth limit gives the same result on
+/// every machine.
 ///
 /// # Arguments
 /// * `board` - The current `Board` state to analyze.
-/// * `time` - The max time the engine can use to search
+/// * `limits` - When to stop searching.
 ///
 /// # Returns
-/// A tuple containing the evaluation score of the best move and the `Coordinates` of that move.
-pub fn search(board: &Board, time: Duration) -> (i32, Coordinates) {
-    let mut current_depth = 1;
-    let maximizing_player = board.turn() == Player::Red;
-    let mut transposition_table = TranspositionTable::new();
-    let placement_evaluations = board.get_valid_moves();
-
-    let mut best_placement = placement_evaluations[0];
-    let mut best_score = if maximizing_player {
-        i32::MIN
-    } else {
-        i32::MAX
-    };
+/// A `SearchResult` with the best move, its score and statistics about the search.
+///
+/// # Panics
+/// Panics if `limits` is infinite, since nothing could stop the search.
+/// Use `search_until_stopped` instead. Also panics if the game is over or the
+/// player to move has no squares, see `search_with_evaluator`.
+pub fn search(board: &Board, limits: SearchLimits) -> SearchResult {
+    assert!(
+        !limits.is_infinite(),
+        "an infinite search needs a stop signal, use `search_until_stopped`"
+    );
+    search_until_stopped(board, limits, &StopHandle::new())
+}
+
+/// Searches like `search`, but also stops as soon as `stop` is set.
+///
+/// This allows infinite searches, which run until another thread calls
+/// `StopHandle::stop`. The result is that of the deepest search completed before
+/// stopping.
+pub fn search_until_stopped(
+    board: &Board,
+    limits: SearchLimits,
+    stop: &StopHandle,
+) -> SearchResult {
+    search_with_progress(
+        board,
+        limits,
+        stop,
+        &mut TranspositionTable::default(),
+        |_| {},
+    )
+}
 
+/// Searches like `search_until_stopped`, calling `on_iteration` after every
+/// completed depth.
+///
+/// Each call receives the result the search would return if it stopped right
+/// then, which is enough to show live depth and score updates.
+///
+/// Results are cached in `transposition_table`. Reusing the same table for every
+/// move of a game lets a search start from what the previous ones found.
+///
+/// # Example
+///
+/// ```
+/// use color_wars::prelude::*;
+///
+/// let dimensions = Dimensions::default();
+/// let board = Board::setup(
+///     dimensions,
+///     &[
+///         Coordinates::new(1, 1, dimensions),
+///         Coordinates::new(3, 3, dimensions),
+///     ],
+/// );
+///
+/// let mut transposition_table = TranspositionTable::default();
+/// let mut depths = Vec::new();
+/// let result = search_with_progress(
+///     &board,
+///     SearchLimits::depth(3),
+///     &StopHandle::new(),
+///     &mut transposition_table,
+///     |info| depths.push(info.depth()),
+/// );
+/// assert_eq!(depths, [1, 2, 3]);
+/// assert_eq!(result.depth(), 3);
+/// ```
+pub fn search_with_progress(
+    board: &Board,
+    limits: SearchLimits,
+    stop: &StopHandle,
+    transposition_table: &mut TranspositionTable,
+    on_iteration: impl FnMut(&SearchResult),
+) -> SearchResult {
+    search_with_evaluator(
+        board,
+        limits,
+        stop,
+        transposition_table,
+        &StandardEvaluator::default(),
+        on_iteration,
+    )
+}
+
+/// Searches like `search_with_progress`, scoring the leaves of the search with
+/// `evaluator` instead of the `StandardEvaluator`.
+///
+/// The transposition table keeps the scores of the evaluator that found them, so it
+/// should be cleared before searching with another evaluator.
+///
+/// # Panics
+/// Panics if the game is over or the player to move has no squares, since there is
+/// no move to search. Every search entry point goes through this function.
+pub fn search_with_evaluator<E: Evaluator>(
+    board: &Board,
+    limits: SearchLimits,
+    stop: &StopHandle,
+    transposition_table: &mut TranspositionTable,
+    evaluator: &E,
+    mut on_iteration: impl FnMut(&SearchResult),
+) -> SearchResult {
+    assert!(
+        !board.is_game_over(),
+        "the game is over, there is no move to search"
+    );
+    assert!(
+        board.valid_moves().next().is_some(),
+        "{} has no squares, there is no move to search",
+        board.turn()
+    );
+    transposition_table.new_search();
+    let transposition_table = &*transposition_table;
     let start = std::time::Instant::now();
 
+    // Lazy SMP: helper threads run the same search as the main thread, and share
+    // what they find through the transposition table. They stop with the main thread.
+    let helpers_stop = StopHandle::new();
+    let helper_nodes = AtomicU64::new(0);
+    let mut result = std::thread::scope(|scope| {
+        for helper in 1..limits.threads() {
+            let (helpers_stop, helper_nodes) = (&helpers_stop, &helper_nodes);
+            scope.spawn(move || {
+                let helper_limits = SearchLimits {
+                    nodes: None,
+                    ..limits
+                };
+                let mut context = SearchContext::new(
+                    board,
+                    helper_limits,
+                    start,
+                    helpers_stop,
+                    transposition_table,
+                    evaluator,
+                );
+                // Starting every other helper one ply deeper keeps them from all
+                // searching the same tree at the same time
+                let mut published_nodes = 0;
+                let result = iterative_deepening(
+                    board,
+                    helper_limits,
+                    &mut context,
+                    1 + helper % 2,
+                    start,
+                    |result| {
+                        helper_nodes.fetch_add(result.nodes - published_nodes, Ordering::Relaxed);
+                        published_nodes = result.nodes;
+                    },
+                );
+                helper_nodes.fetch_add(result.nodes - published_nodes, Ordering::Relaxed);
+            });
+        }
+
+        let mut context =
+            SearchContext::new(board, limits, start, stop, transposition_table, evaluator);
+        let result = iterative_deepening(board, limits, &mut context, 1, start, |result| {
+            on_iteration(&SearchResult {
+                nodes: result.nodes + helper_nodes.load(Ordering::Relaxed),
+                ..result.clone()
+            })
+        });
+        helpers_stop.stop();
+        result
+    });
+
+    result.nodes += helper_nodes.load(Ordering::Relaxed);
+    result.elapsed = start.elapsed();
+    result
+}
+
+/// Searches `board` one ply deeper at a time, from `first_depth` until one of the
+/// `limits` is reached, calling `on_iteration` after every completed depth.
+///
+/// This is the work of a single thread of the search, which started at `start`.
+fn iterative_deepening<E: Evaluator>(
+    board: &Board,
+    limits: SearchLimits,
+    context: &mut SearchContext<E>,
+    first_depth: usize,
+    start: Instant,
+    mut on_iteration: impl FnMut(&SearchResult),
+) -> SearchResult {
+    let mut current_depth = first_depth;
+    let player = board.turn();
+
+    // Until a search completes, suggest the best move found by an earlier search
+    // of this position, or else the first valid move
+    let first_move = context
+        .transposition_table
+        .probe(board.hash_key())
+        .and_then(|entry| entry.best_move)
+        .filter(|best_move| board.get_valid_moves().contains(best_move))
+        .or_else(|| board.valid_moves().next())
+        .expect("the player to move has squares");
+    let score = side_score(static_score(board, context.evaluator), player);
+    let mut result = SearchResult::new(board, first_move, score, 0, vec![first_move]);
+    context.root_move = Some(first_move);
+
+    // The root is visited once, not once per iteration
+    context.visit();
+
     let mut last_iteration_duration = Duration::from_secs(0);
+    let mut previous_score: Option<i32> = None;
     loop {
-        if (start.elapsed() + last_iteration_duration) >= time {
+        if context.aborted
+            || limits
+                .max_depth()
+                .is_some_and(|max_depth| current_depth > max_depth)
+            || limits
+                .max_time()
+                .is_some_and(|time| start.elapsed() + last_iteration_duration >= time)
+        {
             break;
         }
 
-        let iteration_start = std::time::Instant::now();
-        let mut alpha = i32::MIN;
-        let mut beta = i32::MAX;
+        let iteration_start = Instant::now();
 
-        let mut current_best_score_for_depth = if maximizing_player {
-            i32::MIN
-        } else {
-            i32::MAX
+        // The score rarely moves far from one depth to the next, so the search starts
+        // with a narrow window around the previous one, which prunes more, and widens
+        // the side it fails on until the score falls inside
+        let mut window = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = match previous_score {
+            Some(score) if Score::from_raw(score).plies().is_none() => (
+                score.saturating_sub(window).max(LOSS),
+                score.saturating_add(window),
+            ),
+            _ => (LOSS, WIN),
         };
-
-        for current_placement in &placement_evaluations {
-            let current_placement = *current_placement;
-            let board_after_move = board.make_move(current_placement);
-            let score = alpha_beta_prunning(
-                &board_after_move,
-                current_depth - 1,
-                alpha,
-                beta,
-                !maximizing_player,
-                &mut transposition_table,
-            );
-
-            if maximizing_player {
-                if score > current_best_score_for_depth {
-                    current_best_score_for_depth = score;
-                    // Only update the final best_placement if we complete a full search at this depth
-                    best_placement = current_placement;
-                }
-                alpha = alpha.max(current_best_score_for_depth); // Update alpha for the *next sibling's* search window
+        let score = loop {
+            let score = negamax(board, current_depth, alpha, beta, context, 0);
+            if context.aborted {
+                break score;
+            }
+            window = window.saturating_mul(2);
+            if score <= alpha && alpha > LOSS {
+                alpha = score.saturating_sub(window).max(LOSS);
+            } else if score >= beta && beta < WIN {
+                beta = score.saturating_add(window);
             } else {
-                // Minimizing player
-                if score < current_best_score_for_depth {
-                    current_best_score_for_depth = score;
-                    best_placement = current_placement;
-                }
-                beta = beta.min(current_best_score_for_depth); // Update beta for the *next sibling's* search window
+                break score;
             }
-        }
+        };
 
-        // The best score from the completed search becomes the official best_score
-        best_score = current_best_score_for_depth;
+        // Only a completed search at this depth replaces the previous results
+        if context.aborted {
+            break;
+        }
+        previous_score = Some(score);
+        let principal_variation = context.line(0).clone();
+        result = SearchResult {
+            nodes: context.nodes,
+            elapsed: start.elapsed(),
+            ..SearchResult::new(
+                board,
+                principal_variation
+                    .first()
+                    .copied()
+                    .unwrap_or(result.best_move),
+                side_score(Score::from_raw(score), player),
+                current_depth,
+                principal_variation,
+            )
+        };
+        context.root_move = Some(result.best_move);
+        on_iteration(&result);
 
         current_depth += 1;
         last_iteration_duration = iteration_start.elapsed();
     }
-    println!("Searched to depth: {}", current_depth - 1); // -1 because we increment after the last successful search
 
-    (best_score, best_placement)
+    result.nodes = context.nodes;
+    result.elapsed = start.elapsed();
+    result
 }
 
-/// The recursive core of the minimax algorithm with alpha-beta pruning.
+/// The best score there is, winning on the spot. The search works with the raw
+/// values of scores, which it can offset for its windows.
+const WIN: i32 = Score::win_in(0).raw();
+
+/// The worst score there is, losing on the spot.
+const LOSS: i32 = -WIN;
+
+/// The number of explosive moves `quiescence` may expand from each leaf of the search.
+const QUIESCENCE_NODES: u64 = 128;
+
+/// How many nodes the search visits between two readings of the clock.
+const TIME_CHECK_NODES: u64 = 1024;
+
+/// The half-width of the first window searched around the previous iteration's score.
+const ASPIRATION_WINDOW: i32 = 25;
+
+/// Returns whether `player` plays on Red's side, since Red is scored against all of
+/// its opponents combined.
+pub(crate) fn is_red_side(player: Player) -> bool {
+    player == Player::Red
+}
+
+/// Converts a score from Red's perspective to the side of `player`, or back.
+pub(crate) fn side_score(score: Score, player: Player) -> Score {
+    if is_red_side(player) { score } else { -score }
+}
+
+/// Scores `board` from the side of the player to move without searching it: a
+/// finished game is a win or a loss on the spot, other positions are left to
+/// `evaluator`.
+fn static_score(board: &Board, evaluator: &impl Evaluator) -> Score {
+    match board.winner() {
+        Some(winner) if is_red_side(winner) == is_red_side(board.turn()) => Score::win_in(0),
+        Some(_) => Score::loss_in(0),
+        None => evaluator.evaluate(board),
+    }
+}
+
+/// The recursive core of the search, a negamax with alpha-beta pruning.
+///
+/// The score is from the side of the player to move, so each side maximizes its
+/// own score and the score of a position after a move is negated when the move
+/// hands the turn to the other side. Wins and losses are counted from the root, so
+/// the sooner wins and the later losses score best. Moves after the first are searched with
+/// principal variation search: a null window only proves they are no better than
+/// the best move so far, and they are searched again when they are.
 ///
-/// This function explores the game tree to find the best possible score from a given
-/// board state, pruning branches that are probably suboptimal.
-fn alpha_beta_prunning(
+/// The moves leading to the score are written to the context's line at `ply`, the
+/// distance from the root, which is left empty for scores that were not searched
+/// here. The search does not allocate once the lines and move lists have grown to
+/// the search depth.
+fn negamax<E: Evaluator>(
     board: &Board,
     depth: usize,
     mut alpha: i32,
     mut beta: i32,
-    maximizing_player: bool,
-    transposition_table: &mut TranspositionTable,
+    context: &mut SearchContext<E>,
+    ply: usize,
 ) -> i32 {
-    if depth == 0 || board.is_game_over() {
-        return evaluate(board);
+    context.line(ply).clear();
+    if ply > 0 && context.visit() {
+        return 0;
     }
 
-    if let Some((cached_score, cached_depth)) = transposition_table.get(board)
-        && *cached_depth >= depth
+    let player = board.turn();
+    if board.is_game_over() {
+        return static_score(board, context.evaluator)
+            .plies_earlier(ply)
+            .raw();
+    }
+    if depth == 0 {
+        context.quiescence_budget = QUIESCENCE_NODES;
+        return quiescence(board, alpha, beta, context, ply);
+    }
+
+    // The root always searches its moves, so it knows the line behind its score
+    let key = board.hash_key();
+    let entry = context.transposition_table.probe(key);
+    if let Some(entry) = entry
+        && entry.depth >= depth
+        && ply > 0
     {
-        return *cached_score;
-    };
+        // The table counts wins and losses from the position, not from the root
+        let score = Score::from_raw(entry.score).plies_earlier(ply).raw();
+        match entry.bound {
+            Bound::Exact => {
+                context.line(ply).extend(entry.best_move);
+                return score;
+            }
+            Bound::Lower => alpha = alpha.max(score),
+            Bound::Upper => beta = beta.min(score),
+        }
+        if alpha >= beta {
+            return score;
+        }
+    }
+    let (original_alpha, original_beta) = (alpha, beta);
 
-    if maximizing_player {
-        let mut value = -i32::MAX;
+    // The best move of an earlier search is the most likely to cause a cutoff, at the
+    // root it is the best move of the previous iteration
+    let hash_move =
+        if ply == 0 { context.root_move } else { None }.or(entry.and_then(|entry| entry.best_move));
+    let moves = context.order_moves(board, ply, hash_move);
 
-        for current_move in board.get_valid_moves() {
-            let board_after_move = board.make_move(current_move);
-            value = value.max(alpha_beta_prunning(
+    let mut value = LOSS;
+    for (index, &(_, current_move)) in moves.iter().enumerate() {
+        let board_after_move = board.make_move(current_move);
+        let mut score = alpha;
+        if index > 0 {
+            score = score_move(
+                player,
                 &board_after_move,
                 depth - 1,
                 alpha,
-                beta,
-                false,
-                transposition_table,
-            ));
-            if value >= beta {
-                break; // beta cutoff
-            }
-            alpha = alpha.max(value);
+                alpha + 1,
+                context,
+                ply + 1,
+            );
         }
-        transposition_table.insert(board.clone(), (value, depth));
-        value
-    } else {
-        let mut value = i32::MAX;
-
-        for current_move in board.get_valid_moves() {
-            let board_after_move = board.make_move(current_move);
-            value = value.min(alpha_beta_prunning(
+        if index == 0 || (score > alpha && score < beta && !context.aborted) {
+            score = score_move(
+                player,
                 &board_after_move,
                 depth - 1,
                 alpha,
                 beta,
-                true,
-                transposition_table,
-            ));
-            if value <= alpha {
-                break; // alpha cutoff
+                context,
+                ply + 1,
+            );
+        }
+        if context.aborted {
+            return 0;
+        }
+
+        if index == 0 || score > value {
+            value = score;
+            context.set_line(ply, current_move);
+        }
+        if value >= beta {
+            context.record_cutoff(player, ply, depth, current_move);
+            break; // beta cutoff
+        }
+        alpha = alpha.max(value);
+    }
+    context.restore_moves(ply, moves);
+
+    let bound = if value <= original_alpha {
+        Bound::Upper
+    } else if value >= original_beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    let best_move = context.line(ply).first().copied();
+    let score = Score::from_raw(value).plies_later(ply).raw();
+    context
+        .transposition_table
+        .store(key, depth, score, bound, best_move);
+    value
+}
+
+/// Extends the search past its depth with explosive moves only, until the position
+/// is quiet, so a chain reaction one move past the horizon is not missed.
+///
+/// The player to move can also stand pat, keeping the evaluation of the position,
+/// since they are not forced to start a chain reaction. Each leaf of the main search
+/// expands at most `QUIESCENCE_NODES` moves, after which positions are evaluated as
+/// they are. The score is from the side of the player to move, like `negamax`.
+fn quiescence<E: Evaluator>(
+    board: &Board,
+    mut alpha: i32,
+    beta: i32,
+    context: &mut SearchContext<E>,
+    ply: usize,
+) -> i32 {
+    let player = board.turn();
+    let stand_pat = static_score(board, context.evaluator)
+        .plies_earlier(ply)
+        .raw();
+    if stand_pat >= beta || board.is_game_over() {
+        return stand_pat;
+    }
+    alpha = alpha.max(stand_pat);
+
+    let mut value = stand_pat;
+    for current_move in board
+        .valid_moves()
+        .filter(|current_move| is_explosive(board, *current_move))
+    {
+        if context.quiescence_budget == 0 {
+            break;
+        }
+        context.quiescence_budget -= 1;
+        if context.visit() {
+            return 0;
+        }
+
+        let board_after_move = board.make_move(current_move);
+        let score = if is_red_side(player) == is_red_side(board_after_move.turn()) {
+            quiescence(&board_after_move, alpha, beta, context, ply + 1)
+        } else {
+            -quiescence(&board_after_move, -beta, -alpha, context, ply + 1)
+        };
+        if context.aborted {
+            return 0;
+        }
+
+        value = value.max(score);
+        if value >= beta {
+            break; // beta cutoff
+        }
+        alpha = alpha.max(value);
+    }
+    value
+}
+
+/// Returns whether `current_move` pops a square next to a square of another player,
+/// starting a chain reaction that captures.
+pub(crate) fn is_explosive(board: &Board, current_move: Coordinates) -> bool {
+    let grid = board.grid();
+    grid[current_move.row()][current_move.column()].value() == 3
+        && current_move.neighbors(board.dimensions()).any(|neighbor| {
+            grid[neighbor.row()][neighbor.column()]
+                .owner()
+                .is_some_and(|owner| owner != board.turn())
+        })
+}
+
+/// Searches the position after `player` moved, scored from `player`'s side.
+fn score_move<E: Evaluator>(
+    player: Player,
+    board_after_move: &Board,
+    depth: usize,
+    alpha: i32,
+    beta: i32,
+    context: &mut SearchContext<E>,
+    ply: usize,
+) -> i32 {
+    if is_red_side(player) == is_red_side(board_after_move.turn()) {
+        negamax(board_after_move, depth, alpha, beta, context, ply)
+    } else {
+        -negamax(board_after_move, depth, -beta, -alpha, context, ply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dimensions::Dimensions,
+        eval_params::EvalParams,
+        evaluator::{CriticalMassEvaluator, evaluate},
+        square::Square,
+    };
+
+    fn setup_test_board() -> Board {
+        let dimensions = Dimensions::default();
+        Board::setup(
+            dimensions,
+            &[
+                Coordinates::new(1, 1, dimensions),
+                Coordinates::new(3, 3, dimensions),
+            ],
+        )
+    }
+
+    #[test]
+    fn search_reports_statistics() {
+        let board = setup_test_board();
+        let result = search(&board, SearchLimits::time(Duration::from_millis(50)));
+
+        assert!(board.get_valid_moves().contains(&result.best_move()));
+        assert_eq!(result.principal_variation()[0], result.best_move());
+        assert!(result.principal_variation().len() <= result.depth());
+        assert!(result.depth() > 0);
+        assert!(result.nodes() > 1);
+        assert!(!result.is_proven_win());
+        assert!(!result.is_proven_loss());
+    }
+
+    // A plain minimax without pruning or caching, from Red's perspective, `ply`
+    // plies away from the root
+    fn minimax(board: &Board, depth: usize, ply: usize) -> Score {
+        minimax_with(&StandardEvaluator::default(), board, depth, ply)
+    }
+
+    // `minimax` scoring its leaves with `evaluator`
+    fn minimax_with(evaluator: &impl Evaluator, board: &Board, depth: usize, ply: usize) -> Score {
+        if board.is_game_over() {
+            return side_score(static_score(board, evaluator), board.turn()).plies_earlier(ply);
+        }
+        if depth == 0 {
+            return quiescence_minimax(evaluator, board, ply);
+        }
+        let scores = board.valid_moves().map(|current_move| {
+            minimax_with(
+                evaluator,
+                &board.make_move(current_move),
+                depth - 1,
+                ply + 1,
+            )
+        });
+        if board.turn() == Player::Red {
+            scores.max().unwrap()
+        } else {
+            scores.min().unwrap()
+        }
+    }
+
+    // The quiescence search of `minimax`, without pruning or a budget
+    fn quiescence_minimax(evaluator: &impl Evaluator, board: &Board, ply: usize) -> Score {
+        let stand_pat = side_score(static_score(board, evaluator), board.turn()).plies_earlier(ply);
+        if board.is_game_over() {
+            return stand_pat;
+        }
+        let scores = board
+            .valid_moves()
+            .filter(|current_move| is_explosive(board, *current_move))
+            .map(|current_move| {
+                quiescence_minimax(evaluator, &board.make_move(current_move), ply + 1)
+            });
+        if board.turn() == Player::Red {
+            scores.fold(stand_pat, Score::max)
+        } else {
+            scores.fold(stand_pat, Score::min)
+        }
+    }
+
+    #[test]
+    fn search_matches_minimax() {
+        let dimensions = Dimensions::default();
+        let mut boards = vec![
+            setup_test_board(),
+            Board::setup(
+                dimensions,
+                &[
+                    Coordinates::new(0, 0, dimensions),
+                    Coordinates::new(2, 2, dimensions),
+                    Coordinates::new(4, 4, dimensions),
+                ],
+            ),
+        ];
+        // Positions with more squares, where the score moves between depths
+        for board in boards.clone() {
+            let mut board = board;
+            for _ in 0..6 {
+                board = board.make_move(board.get_valid_moves()[0]);
+            }
+            boards.push(board);
+        }
+
+        for board in &boards {
+            for depth in 1..=4 {
+                let result = search(board, SearchLimits::depth(depth));
+                assert_eq!(result.score(), minimax(board, depth, 0), "depth {depth}");
+            }
+        }
+    }
+
+    #[test]
+    fn search_with_other_evaluators() {
+        let mut board = setup_test_board();
+        for _ in 0..6 {
+            board = board.make_move(board.get_valid_moves()[0]);
+        }
+
+        for depth in 1..=4 {
+            let result = search_with_evaluator(
+                &board,
+                SearchLimits::depth(depth),
+                &StopHandle::new(),
+                &mut TranspositionTable::default(),
+                &CriticalMassEvaluator,
+                |_| {},
+            );
+            assert_eq!(
+                result.score(),
+                minimax_with(&CriticalMassEvaluator, &board, depth, 0),
+                "depth {depth}"
+            );
+        }
+
+        // Without a heuristic only wins and losses tell the moves apart
+        let result = search_with_evaluator(
+            &board,
+            SearchLimits::depth(3),
+            &StopHandle::new(),
+            &mut TranspositionTable::default(),
+            &|_: &Board| Score::evaluation(0),
+            |_| {},
+        );
+        assert_eq!(result.score(), Score::evaluation(0));
+        assert_eq!(result.best_move(), board.get_valid_moves()[0]);
+    }
+
+    #[test]
+    fn quiescence_sees_chain_reactions_past_the_horizon() {
+        // Whatever Red plays, Blue's square pops next and captures every Red square
+        let board: Board = "r1b3r1/... r".parse().unwrap();
+        // Stopping at the depth evaluates the positions before the pop
+        let horizon = board
+            .valid_moves()
+            .map(|current_move| evaluate(&board.make_move(current_move), &EvalParams::default()))
+            .max()
+            .unwrap();
+
+        let result = search(&board, SearchLimits::depth(1));
+        assert!(!horizon.is_loss());
+        assert_eq!(result.score(), Score::loss_in(2));
+        assert!(result.is_proven_loss());
+        assert_eq!(result.score(), minimax(&board, 1, 0));
+    }
+
+    #[test]
+    fn explosive_moves() {
+        let board: Board = "r3b1./r1.. r".parse().unwrap();
+        let dimensions = board.dimensions();
+        let explosive: Vec<Coordinates> = board
+            .valid_moves()
+            .filter(|current_move| is_explosive(&board, *current_move))
+            .collect();
+        assert_eq!(explosive, [Coordinates::new(0, 0, dimensions)]);
+    }
+
+    #[test]
+    fn moves_are_ordered_by_hash_move_killers_and_history() {
+        let dimensions = Dimensions::new(2, 3);
+        let board: Board = "r1r1r1/r1r1b1 r".parse().unwrap();
+        let square = |row, column| Coordinates::new(row, column, dimensions);
+        let stop = StopHandle::new();
+        let transposition_table = TranspositionTable::new(1);
+        let evaluator = StandardEvaluator::default();
+        let mut context = SearchContext::new(
+            &board,
+            SearchLimits::infinite(),
+            Instant::now(),
+            &stop,
+            &transposition_table,
+            &evaluator,
+        );
+        let order = |context: &mut SearchContext<StandardEvaluator>, hash_move| {
+            let moves = context.order_moves(&board, 1, hash_move);
+            let order: Vec<Coordinates> = moves.iter().map(|(_, current)| *current).collect();
+            context.restore_moves(1, moves);
+            order
+        };
+
+        // Without any information the moves are in row-major order
+        let row_major = board.get_valid_moves();
+        assert_eq!(order(&mut context, None), row_major);
+
+        // History orders the moves, killers come before it and the hash move first
+        context.record_cutoff(Player::Red, 3, 4, square(1, 1));
+        context.record_cutoff(Player::Red, 1, 1, square(0, 2));
+        context.record_cutoff(Player::Red, 1, 1, square(0, 1));
+        assert_eq!(
+            order(&mut context, Some(square(1, 0))),
+            [
+                square(1, 0),
+                square(0, 1),
+                square(0, 2),
+                square(1, 1),
+                square(0, 0)
+            ]
+        );
+
+        // Killers are kept per ply and history per player
+        context.record_cutoff(Player::Blue, 2, 8, square(0, 0));
+        assert_eq!(
+            order(&mut context, None),
+            [
+                square(0, 1),
+                square(0, 2),
+                square(1, 1),
+                square(0, 0),
+                square(1, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn search_without_time() {
+        let board = setup_test_board();
+        let result = search(&board, SearchLimits::time(Duration::ZERO));
+
+        assert_eq!(result.depth(), 0);
+        assert_eq!(result.nodes(), 1);
+        assert_eq!(result.principal_variation(), &[result.best_move()]);
+        assert!(!result.is_proven_win());
+        assert!(!result.is_proven_loss());
+    }
+
+    #[test]
+    fn search_stops_on_time() {
+        // Positions where a depth takes far longer than the one before, so the search
+        // cannot tell in advance that it will run out of time
+        let limit = Duration::from_millis(100);
+        for (size, moves) in [(5, 10), (5, 30), (9, 40)] {
+            let dimensions = Dimensions::new(size, size);
+            let mut board = Board::setup(
+                dimensions,
+                &[
+                    Coordinates::new(1, 1, dimensions),
+                    Coordinates::new(size - 2, size - 2, dimensions),
+                ],
+            );
+            for index in 0..moves {
+                let valid_moves = board.get_valid_moves();
+                board = board.make_move(valid_moves[index % valid_moves.len()]);
             }
-            beta = beta.min(value);
+
+            let result = search(&board, SearchLimits::time(limit));
+            assert!(result.depth() > 0);
+            assert!(
+                result.elapsed() < limit + Duration::from_millis(50),
+                "searched {size}x{size} for {:?}",
+                result.elapsed()
+            );
         }
-        transposition_table.insert(board.clone(), (value, depth));
-        value
+    }
+
+    #[test]
+    fn search_proves_wins() {
+        let dimensions = Dimensions::new(2, 2);
+        let winning_move = Coordinates::new(0, 0, dimensions);
+
+        let red = search(&"r3b1/.. r".parse().unwrap(), SearchLimits::depth(1));
+        assert_eq!(red.best_move(), winning_move);
+        assert_eq!(red.score(), Score::win_in(1));
+        assert!(red.is_proven_win());
+        assert!(!red.is_proven_loss());
+
+        let blue = search(&"b3r1/.. b".parse().unwrap(), SearchLimits::depth(1));
+        assert_eq!(blue.best_move(), winning_move);
+        assert_eq!(blue.score(), Score::loss_in(1));
+        assert!(blue.is_proven_win());
+        assert!(!blue.is_proven_loss());
+    }
+
+    #[test]
+    fn search_prefers_faster_wins() {
+        let board: Board = "r3b1./r1.. r".parse().unwrap();
+        let result = search(&board, SearchLimits::depth(5));
+        assert_eq!(
+            result.best_move(),
+            Coordinates::new(0, 0, board.dimensions())
+        );
+        assert_eq!(result.score(), Score::win_in(1));
+        assert_eq!(result.score().to_string(), "W1");
+    }
+
+    #[test]
+    fn search_to_fixed_depth() {
+        let board = setup_test_board();
+        let first = search(&board, SearchLimits::depth(3));
+        let second = search(&board, SearchLimits::depth(3));
+
+        assert_eq!(first.depth(), 3);
+        assert_eq!(first.best_move(), second.best_move());
+        assert_eq!(first.score(), second.score());
+        assert_eq!(first.nodes(), second.nodes());
+        assert_eq!(first.principal_variation(), second.principal_variation());
+    }
+
+    #[test]
+    fn search_to_node_limit() {
+        let board = setup_test_board();
+        let unlimited = search(&board, SearchLimits::depth(3));
+        let limited = search(
+            &board,
+            SearchLimits::depth(3).with_nodes(unlimited.nodes() - 1),
+        );
+
+        assert_eq!(limited.nodes(), unlimited.nodes() - 1);
+        assert_eq!(limited.depth(), 2);
+        assert_eq!(
+            limited.best_move(),
+            search(&board, SearchLimits::depth(2)).best_move()
+        );
+    }
+
+    #[test]
+    fn search_until_stopped_stops() {
+        let board = setup_test_board();
+        let stop = StopHandle::new();
+        stop.stop();
+        let result = search_until_stopped(&board, SearchLimits::infinite(), &stop);
+
+        assert_eq!(result.depth(), 0);
+        assert_eq!(result.nodes(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn search_infinite_without_stop() {
+        search(&setup_test_board(), SearchLimits::infinite());
+    }
+
+    #[test]
+    #[should_panic(expected = "the game is over")]
+    fn search_finished_game() {
+        search(&"r1../... r".parse().unwrap(), SearchLimits::depth(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Blue has no squares")]
+    fn search_without_moves() {
+        // The parser rejects this position, so it is built square by square
+        let dimensions = Dimensions::new(2, 3);
+        let mut board = Board::new(dimensions, 3, Player::Blue);
+        board.set_square(
+            Coordinates::new(0, 0, dimensions),
+            Square::occupied(Player::Red, 1),
+        );
+        board.set_square(
+            Coordinates::new(1, 2, dimensions),
+            Square::occupied(Player::Green, 1),
+        );
+        search(&board, SearchLimits::depth(3));
+    }
+
+    #[test]
+    fn stop_handle_stops_a_running_search() {
+        let board = setup_test_board();
+        let stop = StopHandle::new();
+        let stopper = stop.clone();
+
+        let result = search_with_progress(
+            &board,
+            SearchLimits::infinite(),
+            &stop,
+            &mut TranspositionTable::default(),
+            |info| {
+                if info.depth() == 2 {
+                    stopper.stop();
+                }
+            },
+        );
+        assert!(stop.is_stopped());
+        assert_eq!(result.depth(), 2);
+
+        stop.reset();
+        assert!(!stop.is_stopped());
+    }
+
+    #[test]
+    fn stop_handle_works_across_threads() {
+        let board = setup_test_board();
+        let stop = StopHandle::new();
+        let stopper = stop.clone();
+
+        let handle = std::thread::spawn(move || {
+            search_until_stopped(&board, SearchLimits::infinite(), &stop)
+        });
+        std::thread::sleep(Duration::from_millis(20));
+        stopper.stop();
+
+        let result = handle.join().unwrap();
+        assert!(result.depth() > 0);
+    }
+
+    #[test]
+    fn progress_reports_every_completed_depth() {
+        let board = setup_test_board();
+        let mut reports = Vec::new();
+        let result = search_with_progress(
+            &board,
+            SearchLimits::depth(3),
+            &StopHandle::new(),
+            &mut TranspositionTable::default(),
+            |info| {
+                reports.push(info.clone());
+            },
+        );
+
+        let depths: Vec<usize> = reports.iter().map(SearchResult::depth).collect();
+        assert_eq!(depths, [1, 2, 3]);
+        assert_eq!(reports[2].best_move(), result.best_move());
+        assert_eq!(reports[2].nodes(), result.nodes());
+    }
+
+    #[test]
+    fn single_thread_is_the_default() {
+        let board = setup_test_board();
+        let default = search(&board, SearchLimits::depth(4));
+        let single = search(&board, SearchLimits::depth(4).with_threads(1));
+
+        assert_eq!(SearchLimits::depth(4).threads(), 1);
+        assert_eq!(single.best_move(), default.best_move());
+        assert_eq!(single.score(), default.score());
+        assert_eq!(single.nodes(), default.nodes());
+        assert_eq!(single.principal_variation(), default.principal_variation());
+    }
+
+    #[test]
+    fn search_on_several_threads() {
+        let board = setup_test_board();
+        let mut depths = Vec::new();
+        let result = search_with_progress(
+            &board,
+            SearchLimits::depth(5).with_threads(4),
+            &StopHandle::new(),
+            &mut TranspositionTable::default(),
+            |info| depths.push(info.depth()),
+        );
+
+        // Progress and the result come from the main thread
+        assert_eq!(depths, [1, 2, 3, 4, 5]);
+        assert_eq!(result.depth(), 5);
+        assert!(board.get_valid_moves().contains(&result.best_move()));
+        assert_eq!(result.principal_variation()[0], result.best_move());
+        assert!(result.nodes() > 1);
+    }
+
+    #[test]
+    fn several_threads_stop_together() {
+        let board = setup_test_board();
+        let stop = StopHandle::new();
+        let stopper = stop.clone();
+
+        let handle = std::thread::spawn(move || {
+            search_until_stopped(&board, SearchLimits::infinite().with_threads(3), &stop)
+        });
+        std::thread::sleep(Duration::from_millis(20));
+        stopper.stop();
+
+        let result = handle.join().unwrap();
+        assert!(result.depth() > 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn search_without_threads() {
+        SearchLimits::depth(1).with_threads(0);
+    }
+
+    #[test]
+    fn transposition_table_persists_between_searches() {
+        let board = setup_test_board();
+        let mut transposition_table = TranspositionTable::default();
+        let mut search_with_table = |limits| {
+            search_with_progress(
+                &board,
+                limits,
+                &StopHandle::new(),
+                &mut transposition_table,
+                |_| {},
+            )
+        };
+
+        let first = search_with_table(SearchLimits::depth(3));
+        let second = search_with_table(SearchLimits::depth(3));
+        assert_eq!(second.best_move(), first.best_move());
+        assert_eq!(second.score(), first.score());
+        assert!(second.nodes() < first.nodes());
+        assert!(!transposition_table.is_empty());
     }
 }
@@ -0,0 +1,195 @@
+//! Defines `EvalParams`, the weights of the `StandardEvaluator`, and the text format
+//! they are saved in.
+use std::{error::Error, fmt, fs, io, path::Path, str::FromStr};
+
+/// The weights of the terms of the `StandardEvaluator`.
+///
+/// The defaults are the hand-picked weights the engine always used, the `tune`
+/// binary fits better ones from self-play games.
+///
+/// Parameters are saved as text, one `name = value` line per weight, in the format
+/// of `Display`. Blank lines and lines starting with `#` are ignored, and weights
+/// missing from the text keep their default.
+///
+/// # Example
+///
+/// ```
+/// use color_wars::prelude::*;
+///
+/// let params: EvalParams = "square = 3\ntied_neighbor = 8".parse().unwrap();
+/// assert_eq!(params.square(), 3);
+/// assert_eq!(params.stronger_neighbor(), EvalParams::default().stronger_neighbor());
+/// assert_eq!(params.to_string().parse(), Ok(params));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvalParams {
+    square: i32,
+    stronger_neighbor: i32,
+    tied_neighbor: i32,
+}
+
+impl EvalParams {
+    // The names of the weights in the text format, in the order of `weights`.
+    const NAMES: [&'static str; 3] = ["square", "stronger_neighbor", "tied_neighbor"];
+
+    /// Creates parameters from their weights.
+    pub fn new(square: i32, stronger_neighbor: i32, tied_neighbor: i32) -> Self {
+        Self {
+            square,
+            stronger_neighbor,
+            tied_neighbor,
+        }
+    }
+
+    /// Returns what each square is worth to its owner.
+    pub fn square(&self) -> i32 {
+        self.square
+    }
+
+    /// Returns what a square is worth next to an opponent's square holding a
+    /// smaller value.
+    pub fn stronger_neighbor(&self) -> i32 {
+        self.stronger_neighbor
+    }
+
+    /// Returns what a square is worth next to an opponent's square holding the same
+    /// value, to the player whose turn it is. It costs as much to the other player.
+    pub fn tied_neighbor(&self) -> i32 {
+        self.tied_neighbor
+    }
+
+    /// Reads parameters saved with `save`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, or with the kind
+    /// `io::ErrorKind::InvalidData` if it does not hold parameters.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// Writes the parameters to the file at `path`, replacing it.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// Returns the weights in the order of `NAMES`, for the tuner to adjust.
+    pub(crate) fn weights(&self) -> [i32; 3] {
+        [self.square, self.stronger_neighbor, self.tied_neighbor]
+    }
+
+    /// Creates parameters from weights in the order of `NAMES`.
+    pub(crate) fn from_weights([square, stronger_neighbor, tied_neighbor]: [i32; 3]) -> Self {
+        Self::new(square, stronger_neighbor, tied_neighbor)
+    }
+}
+
+/// The weights `evaluate` was written with.
+impl Default for EvalParams {
+    fn default() -> Self {
+        Self::new(1, 10, 10)
+    }
+}
+
+/// Formats the parameters as one `name = value` line per weight.
+impl fmt::Display for EvalParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, weight) in Self::NAMES.iter().zip(self.weights()) {
+            writeln!(f, "{name} = {weight}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The error returned when a line of text does not set a known weight.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseEvalParamsError(String);
+
+/// A way to print to terminal why the parameters could not be parsed
+impl fmt::Display for ParseEvalParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid evaluation parameter `{}`, expected `NAME = INTEGER` with NAME one of {}",
+            self.0,
+            EvalParams::NAMES.join(", ")
+        )
+    }
+}
+
+impl Error for ParseEvalParamsError {}
+
+/// Parses parameters written as `name = value` lines, see `EvalParams`.
+impl FromStr for EvalParams {
+    type Err = ParseEvalParamsError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut weights = Self::default().weights();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = || ParseEvalParamsError(line.to_string());
+
+            let (name, value) = line.split_once('=').ok_or_else(error)?;
+            let index = Self::NAMES
+                .iter()
+                .position(|known| *known == name.trim())
+                .ok_or_else(error)?;
+            weights[index] = value.trim().parse().map_err(|_| error())?;
+        }
+        Ok(Self::from_weights(weights))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_round_trip() {
+        let params = EvalParams::new(2, -3, 15);
+        assert_eq!(
+            params.to_string(),
+            "square = 2\nstronger_neighbor = -3\ntied_neighbor = 15\n"
+        );
+        assert_eq!(params.to_string().parse(), Ok(params));
+    }
+
+    #[test]
+    fn comments_and_missing_weights() {
+        let params: EvalParams = "# tuned\n\n  stronger_neighbor=7  \n".parse().unwrap();
+        assert_eq!(params, EvalParams::new(1, 7, 10));
+        assert_eq!("".parse(), Ok(EvalParams::default()));
+    }
+
+    #[test]
+    fn invalid_lines() {
+        assert_eq!(
+            "square = 1\nmobility = 4".parse::<EvalParams>(),
+            Err(ParseEvalParamsError("mobility = 4".to_string()))
+        );
+        assert!("square 1".parse::<EvalParams>().is_err());
+        assert!("square = one".parse::<EvalParams>().is_err());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("color_wars_eval_params_{}.txt", std::process::id()));
+        let params = EvalParams::new(4, 9, 11);
+        params.save(&path).unwrap();
+        assert_eq!(EvalParams::load(&path).unwrap(), params);
+
+        fs::write(&path, "square = many").unwrap();
+        let error = EvalParams::load(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+    }
+}
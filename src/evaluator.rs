@@ -0,0 +1,308 @@
+//! Defines the `Evaluator` trait, which scores positions at the leaves of the search,
+//! and the evaluators that ship with the crate.
+use std::collections::HashSet;
+
+use crate::{
+    board::Board,
+    coordinates::Coordinates,
+    dimensions::Dimensions,
+    engine::{is_red_side, side_score},
+    eval_params::EvalParams,
+    player::Player,
+    score::Score,
+};
+
+/// Scores a position without searching it, for the search to compare the positions
+/// at its leaves.
+///
+/// The score is from the side of the player to move. Red plays against all of its
+/// opponents combined, so in games with more than two players a position is scored
+/// for Red when Red is to move and for Red's opponents together otherwise. The search
+/// scores finished games itself, so evaluators only see games still being played.
+///
+/// Evaluators are shared by the threads of a search, hence `Sync`. Closures taking a
+/// `&Board` are evaluators too.
+///
+/// # Example
+///
+/// ```
+/// use color_wars::prelude::*;
+///
+/// let dimensions = Dimensions::default();
+/// let board = Board::setup(
+///     dimensions,
+///     &[
+///         Coordinates::new(1, 1, dimensions),
+///         Coordinates::new(3, 3, dimensions),
+///     ],
+/// );
+///
+/// let result = search_with_evaluator(
+///     &board,
+///     SearchLimits::depth(3),
+///     &StopHandle::new(),
+///     &mut TranspositionTable::default(),
+///     &CriticalMassEvaluator,
+///     |_| {},
+/// );
+/// assert_eq!(result.depth(), 3);
+/// assert!(CriticalMassEvaluator.evaluate(&board).value().is_some());
+/// ```
+pub trait Evaluator: Sync {
+    /// Returns the score of `board` for the side to move.
+    fn evaluate(&self, board: &Board) -> Score;
+}
+
+impl<F> Evaluator for F
+where
+    F: Fn(&Board) -> Score + Sync,
+{
+    fn evaluate(&self, board: &Board) -> Score {
+        self(board)
+    }
+}
+
+/// The evaluator `search` uses: the squares each side holds, and who wins the fights
+/// between neighboring squares.
+///
+/// A square next to an opponent's square is worth more when it holds a larger value,
+/// or the same value and it is its owner's turn, since it pops first. How much each
+/// term weighs is set by its `EvalParams`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StandardEvaluator {
+    params: EvalParams,
+}
+
+impl StandardEvaluator {
+    /// Creates an evaluator weighing its terms with `params`.
+    pub fn new(params: EvalParams) -> Self {
+        Self { params }
+    }
+
+    /// Returns the weights of the terms.
+    pub fn params(&self) -> EvalParams {
+        self.params
+    }
+}
+
+impl Evaluator for StandardEvaluator {
+    fn evaluate(&self, board: &Board) -> Score {
+        side_score(evaluate(board, &self.params), board.turn())
+    }
+}
+
+/// An evaluator counting material: every square is worth `SQUARE` plus its value,
+/// and a square at critical mass, a move away from popping, `CRITICAL_MASS` more.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CriticalMassEvaluator;
+
+impl CriticalMassEvaluator {
+    /// What holding a square is worth.
+    pub const SQUARE: i32 = 10;
+
+    /// What a square at critical mass is worth on top of its value.
+    pub const CRITICAL_MASS: i32 = 5;
+
+    /// The value of a square at critical mass.
+    const CRITICAL_VALUE: u8 = 3;
+}
+
+impl Evaluator for CriticalMassEvaluator {
+    fn evaluate(&self, board: &Board) -> Score {
+        let side = is_red_side(board.turn());
+        let score = board
+            .grid()
+            .rows()
+            .flatten()
+            .filter_map(|square| {
+                let owner = square.owner()?;
+                let mut value = Self::SQUARE + i32::from(square.value());
+                if square.value() == Self::CRITICAL_VALUE {
+                    value += Self::CRITICAL_MASS;
+                }
+                Some(if is_red_side(owner) == side {
+                    value
+                } else {
+                    -value
+                })
+            })
+            .sum();
+        Score::evaluation(score)
+    }
+}
+
+/// Evaluates the board state from a static, Red-player perspective.
+///
+/// A positive score is favorable for Red, while a negative score is favorable for Blue.
+/// The magnitude of the score represents the degree of advantage. A finished game is
+/// a win or a loss for Red on the spot, `Score::win_in(0)` or `Score::loss_in(0)`.
+///
+/// In games with more than two players, Red is scored against all of its
+/// opponents combined. The terms are weighted by `params`.
+///
+/// # Returns
+/// A `Score` representing the board state.
+pub(crate) fn evaluate(board: &Board, params: &EvalParams) -> Score {
+    let mut score = 0;
+    let mut red_squares = 0usize;
+    let mut opponent_squares = 0usize;
+
+    let dimensions = board.dimensions();
+    let mut visited_squares = VisitedSquares::new(dimensions);
+
+    for r in 0..dimensions.rows() {
+        for c in 0..dimensions.columns() {
+            if let Some(owner) = board.grid()[r][c].owner() {
+                match owner {
+                    Player::Red => {
+                        score += params.square();
+                        red_squares += 1;
+                    }
+                    Player::Blue | Player::Green | Player::Yellow => {
+                        score -= params.square();
+                        opponent_squares += 1;
+                    }
+                }
+                let our_value = board.grid()[r][c].value();
+                for neighbor in Coordinates::new(r, c, dimensions).neighbors(dimensions) {
+                    if visited_squares.contains(neighbor)
+                        || board.grid()[neighbor.row()][neighbor.column()]
+                            .owner()
+                            .is_none()
+                    {
+                        continue;
+                    }
+
+                    let perimeter_value = if our_value
+                        > board.grid()[neighbor.row()][neighbor.column()].value()
+                    {
+                        params.stronger_neighbor()
+                    } else if our_value == board.grid()[neighbor.row()][neighbor.column()].value() {
+                        if owner == board.turn() {
+                            params.tied_neighbor()
+                        } else {
+                            -params.tied_neighbor()
+                        }
+                    } else {
+                        0
+                    };
+
+                    if board.turn() == Player::Red {
+                        score += perimeter_value;
+                    } else {
+                        score -= perimeter_value;
+                    }
+                    visited_squares.insert(neighbor);
+                }
+            }
+        }
+    }
+
+    // P1 wins
+    if opponent_squares == 0 && red_squares > 0 {
+        return Score::win_in(0);
+    }
+
+    // An opponent wins
+    if red_squares == 0 && opponent_squares > 0 {
+        return Score::loss_in(0);
+    }
+
+    Score::evaluation(score)
+}
+
+/// The squares `evaluate` has already scored.
+///
+/// Boards of up to 128 squares use a bitmask, so evaluating them does not allocate.
+enum VisitedSquares {
+    Mask { mask: u128, columns: usize },
+    Set(HashSet<Coordinates>),
+}
+
+impl VisitedSquares {
+    fn new(dimensions: Dimensions) -> Self {
+        if dimensions.area() <= u128::BITS as usize {
+            VisitedSquares::Mask {
+                mask: 0,
+                columns: dimensions.columns(),
+            }
+        } else {
+            VisitedSquares::Set(HashSet::new())
+        }
+    }
+
+    fn contains(&self, coordinates: Coordinates) -> bool {
+        match self {
+            VisitedSquares::Mask { mask, columns } => {
+                mask & 1 << (coordinates.row() * columns + coordinates.column()) != 0
+            }
+            VisitedSquares::Set(set) => set.contains(&coordinates),
+        }
+    }
+
+    fn insert(&mut self, coordinates: Coordinates) {
+        match self {
+            VisitedSquares::Mask { mask, columns } => {
+                *mask |= 1 << (coordinates.row() * *columns + coordinates.column());
+            }
+            VisitedSquares::Set(set) => {
+                set.insert(coordinates);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_evaluator_scores_the_side_to_move() {
+        let red_to_move: Board = "r2b1./... r".parse().unwrap();
+        let blue_to_move: Board = "r2b1./... b".parse().unwrap();
+        assert_eq!(
+            StandardEvaluator::default().evaluate(&red_to_move),
+            evaluate(&red_to_move, &EvalParams::default())
+        );
+        assert_eq!(
+            StandardEvaluator::default().evaluate(&blue_to_move),
+            -evaluate(&blue_to_move, &EvalParams::default())
+        );
+    }
+
+    #[test]
+    fn critical_mass_evaluator_counts_material() {
+        // Red holds 10 + 3 + 5 and 10 + 1, Blue holds 10 + 2
+        let board: Board = "r3r1./b2.. r".parse().unwrap();
+        assert_eq!(
+            CriticalMassEvaluator.evaluate(&board),
+            Score::evaluation(17)
+        );
+
+        // Blue's side sees the same position negated
+        let board: Board = "r3r1./b2.. b".parse().unwrap();
+        assert_eq!(
+            CriticalMassEvaluator.evaluate(&board),
+            Score::evaluation(-17)
+        );
+    }
+
+    #[test]
+    fn opponents_are_scored_together() {
+        let board: Board = "r1b1g1/... g".parse().unwrap();
+        // Blue and Green both count for the side to move
+        assert_eq!(
+            CriticalMassEvaluator.evaluate(&board),
+            Score::evaluation(11)
+        );
+    }
+
+    #[test]
+    fn closures_are_evaluators() {
+        let board: Board = "r1b1/.. r".parse().unwrap();
+        let evaluator = |board: &Board| Score::evaluation(board.square_count(Player::Red) as i32);
+        assert_eq!(evaluator.evaluate(&board), Score::evaluation(1));
+    }
+}
@@ -0,0 +1,417 @@
+//! Defines the `Game` type, which tracks a whole game from setup to result.
+use std::fmt;
+
+use crate::{
+    board::Board, coordinates::Coordinates, dimensions::Dimensions, move_error::MoveError,
+    player::Player,
+};
+
+/// The outcome of a game, or the lack of one so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameResult {
+    /// More than one player still has squares on the board.
+    Ongoing,
+    /// Only the given player has squares left on the board.
+    Won(Player),
+    /// The players agreed to, or were adjudicated, a draw.
+    Drawn,
+}
+
+/// A way to print to terminal the result information
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameResult::Ongoing => write!(f, "Ongoing"),
+            GameResult::Won(player) => write!(f, "{player} wins"),
+            GameResult::Drawn => write!(f, "Draw"),
+        }
+    }
+}
+
+/// A complete game: the starting placements, every move played and every board reached.
+///
+/// Moves can be undone and redone. Playing a new move after an undo discards the
+/// moves that could have been redone.
+///
+/// With the `serde` feature enabled, a game is serialized as its `dimensions`,
+/// `placements`, every move including those that can be redone, the `move_number`
+/// and whether it was `drawn`. Deserializing replays the moves, so only legal games
+/// can be read back.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GameFields", into = "GameFields"))]
+pub struct Game {
+    placements: Vec<Coordinates>,
+    moves: Vec<Coordinates>,
+    // `boards[i]` is the board after `i` moves, so there is always one more board than moves
+    boards: Vec<Board>,
+    // The number of moves currently applied, moves after it can be redone
+    current: usize,
+    drawn: bool,
+}
+
+impl Game {
+    /// Starts a new game with one starting square per player, see [`Board::setup`].
+    ///
+    /// # Panics
+    /// Panics if the placements are not valid, see [`Game::try_new`] to handle
+    /// those cases without panicking.
+    pub fn new(dimensions: Dimensions, placements: &[Coordinates]) -> Self {
+        match Self::try_new(dimensions, placements) {
+            Ok(game) => game,
+            Err(error) => panic!("invalid setup: {error}"),
+        }
+    }
+
+    /// Starts a new game like [`Game::new`], reporting invalid placements.
+    ///
+    /// # Errors
+    /// Returns the `MoveError` produced by [`Board::try_setup`].
+    pub fn try_new(dimensions: Dimensions, placements: &[Coordinates]) -> Result<Self, MoveError> {
+        let board = Board::try_setup(dimensions, placements)?;
+        Ok(Self {
+            placements: placements.to_vec(),
+            moves: Vec::new(),
+            boards: vec![board],
+            current: 0,
+            drawn: false,
+        })
+    }
+
+    /// Returns the current board.
+    pub fn board(&self) -> &Board {
+        &self.boards[self.current]
+    }
+
+    /// Returns the size of the board the game is played on.
+    pub fn dimensions(&self) -> Dimensions {
+        self.board().dimensions()
+    }
+
+    /// Returns the starting square of every player, in turn order.
+    pub fn placements(&self) -> &[Coordinates] {
+        &self.placements
+    }
+
+    /// Returns the moves played so far, excluding undone moves.
+    pub fn moves(&self) -> &[Coordinates] {
+        &self.moves[..self.current]
+    }
+
+    /// Returns every board reached so far, starting with the board right after setup
+    /// and ending with the current board.
+    pub fn boards(&self) -> &[Board] {
+        &self.boards[..=self.current]
+    }
+
+    /// Returns the number of moves played so far, `0` right after setup.
+    pub fn move_number(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the current result of the game.
+    pub fn result(&self) -> GameResult {
+        if self.drawn {
+            return GameResult::Drawn;
+        }
+        match self.board().winner() {
+            Some(player) => GameResult::Won(player),
+            None => GameResult::Ongoing,
+        }
+    }
+
+    /// Plays a move for the current player, discarding any moves that could be redone.
+    ///
+    /// # Errors
+    /// Returns `MoveError::GameAlreadyOver` if the game has a result, or the `MoveError`
+    /// produced by [`Board::try_make_move`]. The game is left untouched in that case.
+    pub fn play(&mut self, placement: Coordinates) -> Result<(), MoveError> {
+        if self.result() != GameResult::Ongoing {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        let board = self.board().try_make_move(placement)?;
+
+        self.moves.truncate(self.current);
+        self.boards.truncate(self.current + 1);
+
+        self.moves.push(placement);
+        self.boards.push(board);
+        self.current += 1;
+
+        Ok(())
+    }
+
+    /// Ends an ongoing game as a draw. Undoing a move lifts the draw.
+    ///
+    /// # Errors
+    /// Returns `MoveError::GameAlreadyOver` if the game already has a result.
+    pub fn declare_draw(&mut self) -> Result<(), MoveError> {
+        if self.result() != GameResult::Ongoing {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        self.drawn = true;
+        Ok(())
+    }
+
+    /// Checks whether there is a move to undo.
+    pub fn can_undo(&self) -> bool {
+        self.current > 0
+    }
+
+    /// Checks whether there is an undone move to redo.
+    pub fn can_redo(&self) -> bool {
+        self.current < self.moves.len()
+    }
+
+    /// Takes back the last move, returning it if there was one.
+    pub fn undo(&mut self) -> Option<Coordinates> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.drawn = false;
+        self.current -= 1;
+        Some(self.moves[self.current])
+    }
+
+    /// Replays the last undone move, returning it if there was one.
+    pub fn redo(&mut self) -> Option<Coordinates> {
+        if !self.can_redo() {
+            return None;
+        }
+        self.current += 1;
+        Some(self.moves[self.current - 1])
+    }
+}
+
+// The serialized form of a `Game`, replayed before it becomes one.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct GameFields {
+    dimensions: Dimensions,
+    placements: Vec<Coordinates>,
+    moves: Vec<Coordinates>,
+    move_number: usize,
+    drawn: bool,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GameFields> for Game {
+    type Error = String;
+
+    fn try_from(fields: GameFields) -> Result<Self, Self::Error> {
+        if fields.move_number > fields.moves.len() {
+            return Err(format!(
+                "move {} does not exist, there are {} moves",
+                fields.move_number,
+                fields.moves.len()
+            ));
+        }
+
+        let mut game = Game::try_new(fields.dimensions, &fields.placements)
+            .map_err(|error| format!("invalid setup: {error}"))?;
+        for (index, placement) in fields.moves.iter().enumerate() {
+            game.play(*placement)
+                .map_err(|error| format!("move {} is illegal: {error}", index + 1))?;
+        }
+        while game.move_number() > fields.move_number {
+            game.undo();
+        }
+        if fields.drawn {
+            game.declare_draw()
+                .map_err(|error| format!("the game cannot be drawn: {error}"))?;
+        }
+
+        Ok(game)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Game> for GameFields {
+    fn from(game: Game) -> Self {
+        GameFields {
+            dimensions: game.dimensions(),
+            move_number: game.move_number(),
+            drawn: game.drawn,
+            placements: game.placements,
+            moves: game.moves,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Helper function to create a game for testing
+    fn setup_test_game() -> Game {
+        let dimensions = Dimensions::default();
+        Game::new(
+            dimensions,
+            &[
+                Coordinates::new(0, 0, dimensions),
+                Coordinates::new(4, 4, dimensions),
+            ],
+        )
+    }
+
+    #[test]
+    fn new_game() {
+        let game = setup_test_game();
+        assert_eq!(game.move_number(), 0);
+        assert_eq!(game.result(), GameResult::Ongoing);
+        assert_eq!(game.placements().len(), 2);
+        assert!(game.moves().is_empty());
+        assert_eq!(game.boards().len(), 1);
+        assert!(!game.can_undo());
+        assert!(!game.can_redo());
+    }
+
+    #[test]
+    fn try_new_invalid_setup() {
+        let dimensions = Dimensions::default();
+        let corner = Coordinates::new(0, 0, dimensions);
+        assert_eq!(
+            Game::try_new(dimensions, &[corner, corner]),
+            Err(MoveError::SameStartingSquare(corner))
+        );
+    }
+
+    #[test]
+    fn play_records_history() {
+        let mut game = setup_test_game();
+        let dimensions = game.dimensions();
+        let red_move = Coordinates::new(0, 0, dimensions);
+        let blue_move = Coordinates::new(4, 4, dimensions);
+
+        game.play(red_move).unwrap();
+        game.play(blue_move).unwrap();
+
+        assert_eq!(game.move_number(), 2);
+        assert_eq!(game.moves(), [red_move, blue_move]);
+        assert_eq!(game.boards().len(), 3);
+        assert_eq!(game.board(), &game.boards()[1].make_move(blue_move));
+        assert_eq!(game.board().turn(), Player::Red);
+    }
+
+    #[test]
+    fn play_invalid_move() {
+        let mut game = setup_test_game();
+        let before = game.clone();
+        let result = game.play(Coordinates::new(2, 2, game.dimensions()));
+
+        assert!(matches!(result, Err(MoveError::EmptySquare(_))));
+        assert_eq!(game, before);
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut game = setup_test_game();
+        let red_move = Coordinates::new(0, 0, game.dimensions());
+        let start = game.board().clone();
+
+        game.play(red_move).unwrap();
+        let after_move = game.board().clone();
+
+        assert_eq!(game.undo(), Some(red_move));
+        assert_eq!(game.board(), &start);
+        assert_eq!(game.move_number(), 0);
+        assert!(game.moves().is_empty());
+        assert_eq!(game.undo(), None);
+
+        assert_eq!(game.redo(), Some(red_move));
+        assert_eq!(game.board(), &after_move);
+        assert_eq!(game.redo(), None);
+    }
+
+    #[test]
+    fn play_after_undo_discards_redo() {
+        let mut game = setup_test_game();
+        let dimensions = game.dimensions();
+        let red_pop = Coordinates::new(0, 0, dimensions);
+        let blue_pop = Coordinates::new(4, 4, dimensions);
+
+        game.play(red_pop).unwrap();
+        game.play(blue_pop).unwrap();
+
+        // Red now owns (0,1) and (1,0), play one and then replace it with the other
+        game.play(Coordinates::new(0, 1, dimensions)).unwrap();
+        game.undo();
+        let other_move = Coordinates::new(1, 0, dimensions);
+        game.play(other_move).unwrap();
+
+        assert!(!game.can_redo());
+        assert_eq!(game.moves(), [red_pop, blue_pop, other_move]);
+        assert_eq!(game.boards().len(), 4);
+    }
+
+    #[test]
+    fn result_tracks_winner() {
+        let dimensions = Dimensions::new(2, 2);
+        let mut game = Game::new(
+            dimensions,
+            &[
+                Coordinates::new(0, 0, dimensions),
+                Coordinates::new(0, 1, dimensions),
+            ],
+        );
+
+        // Red pops the corner and captures Blue's only square
+        game.play(Coordinates::new(0, 0, dimensions)).unwrap();
+        assert_eq!(game.result(), GameResult::Won(Player::Red));
+        assert_eq!(
+            game.play(Coordinates::new(0, 1, dimensions)),
+            Err(MoveError::GameAlreadyOver)
+        );
+
+        game.undo();
+        assert_eq!(game.result(), GameResult::Ongoing);
+    }
+
+    #[test]
+    fn declare_draw() {
+        let mut game = setup_test_game();
+        game.declare_draw().unwrap();
+
+        assert_eq!(game.result(), GameResult::Drawn);
+        assert_eq!(game.declare_draw(), Err(MoveError::GameAlreadyOver));
+        assert_eq!(
+            game.play(Coordinates::new(0, 0, game.dimensions())),
+            Err(MoveError::GameAlreadyOver)
+        );
+    }
+
+    #[test]
+    fn display_trait() {
+        assert_eq!(GameResult::Ongoing.to_string(), "Ongoing");
+        assert_eq!(GameResult::Won(Player::Blue).to_string(), "Blue wins");
+        assert_eq!(GameResult::Drawn.to_string(), "Draw");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut game = setup_test_game();
+        let dimensions = game.dimensions();
+        game.play(Coordinates::new(0, 0, dimensions)).unwrap();
+        game.play(Coordinates::new(4, 4, dimensions)).unwrap();
+        game.undo();
+
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(
+            json,
+            r#"{"dimensions":{"rows":5,"columns":5},"placements":["a1","e5"],"moves":["a1","e5"],"move_number":1,"drawn":false}"#
+        );
+
+        let mut parsed: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, game);
+        assert_eq!(parsed.redo(), Some(Coordinates::new(4, 4, dimensions)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_illegal_moves() {
+        let json = r#"{"dimensions":{"rows":5,"columns":5},"placements":["a1","e5"],"moves":["e5"],"move_number":1,"drawn":false}"#;
+        assert!(serde_json::from_str::<Game>(json).is_err());
+    }
+}
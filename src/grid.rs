@@ -0,0 +1,214 @@
+//! Defines the `Grid` type, the runtime-sized 2D array of squares that makes up a board.
+use std::{
+    hash::{Hash, Hasher},
+    ops::{Index, IndexMut},
+};
+
+use crate::{dimensions::Dimensions, square::Square};
+
+/// The largest number of squares a grid keeps inline, without a heap allocation.
+const INLINE_SQUARES: usize = 128;
+
+/// A row-major grid of squares whose size is chosen at runtime.
+///
+/// Indexing a `Grid` by a row number yields that row as a slice, so squares
+/// are accessed as `grid[row][column]`.
+///
+/// Grids of up to 128 squares are stored inline, so copying a board during a
+/// search does not allocate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "Vec<Vec<Square>>", into = "Vec<Vec<Square>>")
+)]
+pub struct Grid {
+    dimensions: Dimensions,
+    squares: Squares,
+}
+
+// The storage of a grid's squares, inline when they fit. The inline variant is
+// meant to be large, it is what keeps copying a board free of allocations.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+enum Squares {
+    Inline {
+        squares: [Square; INLINE_SQUARES],
+        len: usize,
+    },
+    Heap(Vec<Square>),
+}
+
+impl Squares {
+    fn as_slice(&self) -> &[Square] {
+        match self {
+            Squares::Inline { squares, len } => &squares[..*len],
+            Squares::Heap(squares) => squares,
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [Square] {
+        match self {
+            Squares::Inline { squares, len } => &mut squares[..*len],
+            Squares::Heap(squares) => squares,
+        }
+    }
+}
+
+impl From<Vec<Square>> for Squares {
+    fn from(squares: Vec<Square>) -> Self {
+        if squares.len() > INLINE_SQUARES {
+            return Squares::Heap(squares);
+        }
+        let mut inline = [Square::empty(); INLINE_SQUARES];
+        inline[..squares.len()].copy_from_slice(&squares);
+        Squares::Inline {
+            squares: inline,
+            len: squares.len(),
+        }
+    }
+}
+
+impl PartialEq for Squares {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for Squares {}
+
+impl Hash for Squares {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+impl Grid {
+    /// Creates a new grid of the given `dimensions` where every square is empty.
+    pub fn new(dimensions: Dimensions) -> Self {
+        Self {
+            dimensions,
+            squares: Squares::from(vec![Square::empty(); dimensions.area()]),
+        }
+    }
+
+    /// Returns the size of the grid.
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    /// Returns an iterator over the rows of the grid, from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[Square]> {
+        self.squares.as_slice().chunks(self.dimensions.columns())
+    }
+}
+
+impl Index<usize> for Grid {
+    type Output = [Square];
+
+    fn index(&self, row: usize) -> &Self::Output {
+        let columns = self.dimensions.columns();
+        &self.squares.as_slice()[row * columns..(row + 1) * columns]
+    }
+}
+
+impl IndexMut<usize> for Grid {
+    fn index_mut(&mut self, row: usize) -> &mut Self::Output {
+        let columns = self.dimensions.columns();
+        &mut self.squares.as_mut_slice()[row * columns..(row + 1) * columns]
+    }
+}
+
+// A grid is serialized as a list of rows, which must form a board of at least 2x2.
+#[cfg(feature = "serde")]
+impl TryFrom<Vec<Vec<Square>>> for Grid {
+    type Error = String;
+
+    fn try_from(rows: Vec<Vec<Square>>) -> Result<Self, Self::Error> {
+        let columns = rows.first().map_or(0, Vec::len);
+        if rows.len() < Dimensions::MIN_SIZE || columns < Dimensions::MIN_SIZE {
+            return Err(format!("a {}x{columns} grid is too small", rows.len()));
+        }
+        if let Some(row) = rows.iter().position(|row| row.len() != columns) {
+            return Err(format!(
+                "row {row} has {} squares, expected {columns}",
+                rows[row].len()
+            ));
+        }
+
+        Ok(Self {
+            dimensions: Dimensions::new(rows.len(), columns),
+            squares: Squares::from(rows.into_iter().flatten().collect::<Vec<_>>()),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Grid> for Vec<Vec<Square>> {
+    fn from(grid: Grid) -> Self {
+        grid.rows().map(<[Square]>::to_vec).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+
+    #[test]
+    fn new_grid_is_empty() {
+        let grid = Grid::new(Dimensions::new(3, 4));
+        assert_eq!(grid.rows().count(), 3);
+        for row in grid.rows() {
+            assert_eq!(row, [Square::empty(); 4]);
+        }
+    }
+
+    #[test]
+    fn index_by_row_and_column() {
+        let mut grid = Grid::new(Dimensions::new(4, 6));
+        grid[3][5] = Square::occupied(Player::Red, 2);
+        grid[1][0] = Square::occupied(Player::Blue, 1);
+
+        assert_eq!(grid[3][5], Square::occupied(Player::Red, 2));
+        assert_eq!(grid[1][0], Square::occupied(Player::Blue, 1));
+        assert_eq!(grid[0][5], Square::empty());
+        assert_eq!(grid[2].len(), 6);
+    }
+
+    #[test]
+    fn large_grids_are_stored_on_the_heap() {
+        let small = Grid::new(Dimensions::new(8, 16));
+        assert!(matches!(small.squares, Squares::Inline { .. }));
+
+        let mut large = Grid::new(Dimensions::new(12, 12));
+        assert!(matches!(large.squares, Squares::Heap(_)));
+        large[11][11] = Square::occupied(Player::Green, 3);
+        assert_eq!(large[11][11], Square::occupied(Player::Green, 3));
+        assert_eq!(large.rows().count(), 12);
+        assert_ne!(large, Grid::new(Dimensions::new(12, 12)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_out_of_range() {
+        let grid = Grid::new(Dimensions::new(3, 3));
+        let _ = grid[3][0];
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut grid = Grid::new(Dimensions::new(2, 3));
+        grid[1][2] = Square::occupied(Player::Blue, 2);
+        let json = serde_json::to_string(&grid).unwrap();
+
+        assert_eq!(
+            json,
+            r#"[[null,null,null],[null,null,{"owner":"Blue","value":2}]]"#
+        );
+        assert_eq!(serde_json::from_str::<Grid>(&json).unwrap(), grid);
+        assert!(serde_json::from_str::<Grid>("[[null,null],[null]]").is_err());
+        assert!(serde_json::from_str::<Grid>("[[null,null]]").is_err());
+    }
+}
@@ -1,23 +1,33 @@
 //! A Rust implementation of the "Color Wars" tactical board game engine.
 //!
 //! This crate provides all the necessary types and logic to run a game of Color Wars.
-//! It includes the game board, player and square representations, and the core
-//! move execution logic that handles the "pop" and chain reaction mechanics.
+//! It includes the game board, player and square representations for games of 2 to 4
+//! players, and the core move execution logic that handles the "pop" and chain reaction
+//! mechanics.
 //!
 //! The easiest way to get started is by importing the commonly used items
 //! from the `prelude` module.
 //!
+//! Enabling the `serde` cargo feature implements `Serialize` and `Deserialize` for
+//! the public types, see [`Board`](prelude::Board) for the JSON shape of a position.
+//!
 //! # Example
 //!
 //! ```
 //! // Import the essentials
 //! use color_wars::prelude::*;
 //!
-//! // Create a new board with a standard setup
-//! let board = Board::setup(Coordinates::new(1, 1), Coordinates::new(2, 2));
+//! // Boards are sized at runtime; the default is the standard 5x5 board
+//! let dimensions = Dimensions::default();
 //!
-//! // Print the initial state
-//! print_board(&board);
+//! // Create a new board with a standard setup
+//! let board = Board::setup(
+//!     dimensions,
+//!     &[
+//!         Coordinates::new(1, 1, dimensions),
+//!         Coordinates::new(2, 2, dimensions),
+//!     ],
+//! );
 //!
 //! // Get the first player's valid moves
 //! let moves = board.get_valid_moves();
@@ -25,32 +35,60 @@
 //! // Make the first move
 //! if let Some(first_move) = moves.get(0) {
 //!     let new_board = board.make_move(*first_move);
-//!     println!("After one move:");
-//!     print_board(&new_board);
+//!     println!("After one move: {:?}", new_board.grid());
 //! }
 //! ```
 
+mod bitboard;
 mod board;
 mod coordinates;
+mod dimensions;
 mod engine;
+mod eval_params;
+mod evaluator;
+mod game;
+mod grid;
+mod mcts;
+mod move_error;
+mod move_trace;
+mod neural_evaluator;
+mod notation;
 mod player;
+mod random;
+mod record;
+mod score;
 mod square;
+mod transposition_table;
+mod tuning;
+mod zobrist;
 
 /// The prelude module provides convenient access to the most common types and functions.
 ///
 /// By importing everything from this module (`use color_wars::prelude::*`), you can
-/// easily access `Board`, `Player`, `Coordinates`, and other essential items.
+/// easily access `Board`, `Player`, `Coordinates`, `Dimensions`, and other essential items.
 pub mod prelude {
-    pub use crate::board::Board;
-    pub use crate::coordinates::Coordinates;
-    pub use crate::engine::search;
+    pub use crate::board::{Board, ValidMoves};
+    pub use crate::coordinates::{Coordinates, ParseCoordinatesError};
+    pub use crate::dimensions::{Dimensions, ParseDimensionsError};
+    pub use crate::engine::{
+        SearchLimits, SearchResult, StopHandle, search, search_until_stopped,
+        search_with_evaluator, search_with_progress,
+    };
+    pub use crate::eval_params::{EvalParams, ParseEvalParamsError};
+    pub use crate::evaluator::{CriticalMassEvaluator, Evaluator, StandardEvaluator};
+    pub use crate::game::{Game, GameResult};
+    pub use crate::grid::Grid;
+    pub use crate::mcts::{MonteCarloResult, MoveStatistics, monte_carlo_search};
+    pub use crate::move_error::MoveError;
+    pub use crate::move_trace::{MoveEvent, MoveTrace};
+    pub use crate::neural_evaluator::{NeuralEvaluator, ParseNeuralEvaluatorError};
+    pub use crate::notation::NotationError;
     pub use crate::player::Player;
+    pub use crate::record::{GameRecord, RecordError, STANDARD_VARIANT};
+    pub use crate::score::Score;
     pub use crate::square::Square;
-    pub use crate::{BOARD_COLUMN_SIZE, BOARD_ROW_SIZE};
+    pub use crate::transposition_table::TranspositionTable;
+    pub use crate::tuning::{
+        TrainingPosition, self_play, self_play_games, training_positions, tune,
+    };
 }
-
-/// The number of rows on the game board. Must be 2 or greater.
-pub const BOARD_ROW_SIZE: usize = 5;
-
-/// The number of columns on the game board. Must be 2 or greater.
-pub const BOARD_COLUMN_SIZE: usize = 5;
@@ -0,0 +1,467 @@
+//! A second AI engine, a Monte Carlo tree search (UCT) that learns which moves win by
+//! playing games out with random moves instead of evaluating positions.
+use std::{
+    f64::consts::SQRT_2,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::Board,
+    coordinates::Coordinates,
+    engine::{self, SearchLimits, StopHandle},
+    player::Player,
+    random::Random,
+};
+
+/// How much the search explores moves it knows little about rather than the ones
+/// that won the most so far.
+const EXPLORATION: f64 = SQRT_2;
+
+/// How many moves per square of the board a playout may last before it is scored
+/// by the squares each player holds.
+const PLAYOUT_MOVES_PER_SQUARE: usize = 4;
+
+/// What the search found out about one move of the searched position.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoveStatistics {
+    placement: Coordinates,
+    visits: u64,
+    wins: f64,
+}
+
+impl MoveStatistics {
+    /// Returns the move.
+    pub fn placement(&self) -> Coordinates {
+        self.placement
+    }
+
+    /// Returns the number of playouts that started with the move.
+    pub fn visits(&self) -> u64 {
+        self.visits
+    }
+
+    /// Returns the number of those playouts the player to move won. A playout
+    /// stopped before the end of the game is shared between the leading players.
+    pub fn wins(&self) -> f64 {
+        self.wins
+    }
+
+    /// Returns the share of the playouts the player to move won, `0.0` for a move
+    /// that was never played out.
+    pub fn win_rate(&self) -> f64 {
+        if self.visits == 0 {
+            return 0.0;
+        }
+        self.wins / self.visits as f64
+    }
+}
+
+/// The outcome of a call to `monte_carlo_search`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MonteCarloResult {
+    best_move: Coordinates,
+    playouts: u64,
+    elapsed: Duration,
+    moves: Vec<MoveStatistics>,
+}
+
+impl MonteCarloResult {
+    /// Returns the move the engine recommends, the one it played out the most.
+    pub fn best_move(&self) -> Coordinates {
+        self.best_move
+    }
+
+    /// Returns the number of games played out.
+    pub fn playouts(&self) -> u64 {
+        self.playouts
+    }
+
+    /// Returns how long the search took.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the statistics of every move of the searched position, in the order
+    /// of `Board::get_valid_moves`.
+    pub fn moves(&self) -> &[MoveStatistics] {
+        &self.moves
+    }
+}
+
+/// Searches `board` with a Monte Carlo tree search until one of the `limits` is
+/// reached or `stop` is set, and returns the move played out the most.
+///
+/// Each playout follows the most promising moves of the tree by their UCT value,
+/// adds a position to it and plays the game out with random moves, preferring the
+/// ones that start a chain reaction. Every player tries to win on their own, so the
+/// search also suits games of more than two players.
+///
+/// The node limit counts playouts. The search grows a single tree with no fixed
+/// depth, so the depth limit and the number of threads of `limits` are ignored, and
+/// without a node or time limit the search runs until `stop` is set, like
+/// `search_until_stopped`. The random moves are seeded by the position, so a search
+/// limited by playouts always gives the same result.
+///
+/// # Panics
+/// Panics if the game on `board` is over, since there is no move to recommend.
+///
+/// # Example
+///
+/// ```
+/// use color_wars::prelude::*;
+///
+/// let dimensions = Dimensions::default();
+/// let board = Board::setup(
+///     dimensions,
+///     &[
+///         Coordinates::new(1, 1, dimensions),
+///         Coordinates::new(3, 3, dimensions),
+///     ],
+/// );
+///
+/// let result = monte_carlo_search(&board, SearchLimits::nodes(200), &StopHandle::new());
+/// assert_eq!(result.playouts(), 200);
+/// for statistics in result.moves() {
+///     println!("{}: {:.0}%", statistics.placement(), statistics.win_rate() * 100.0);
+/// }
+/// ```
+pub fn monte_carlo_search(
+    board: &Board,
+    limits: SearchLimits,
+    stop: &StopHandle,
+) -> MonteCarloResult {
+    assert!(
+        !board.is_game_over(),
+        "the game is over, there is no move to search"
+    );
+
+    let start = Instant::now();
+    let mut tree = Tree::new(board);
+    let mut random = Random::new(board.hash_key());
+
+    let mut playouts = 0;
+    while !stop.is_stopped()
+        && limits
+            .max_nodes()
+            .is_none_or(|max_nodes| playouts < max_nodes)
+        && limits.max_time().is_none_or(|time| start.elapsed() < time)
+    {
+        tree.playout(&mut random);
+        playouts += 1;
+    }
+
+    let moves = tree.root_statistics(board);
+    let best_move = moves
+        .iter()
+        .reduce(|best, current| {
+            if current.visits > best.visits {
+                current
+            } else {
+                best
+            }
+        })
+        .map(MoveStatistics::placement)
+        .expect("a game that is not over has a move");
+    MonteCarloResult {
+        best_move,
+        playouts,
+        elapsed: start.elapsed(),
+        moves,
+    }
+}
+
+/// A position of the search tree.
+struct Node {
+    board: Board,
+    // The move that led here from the parent, `None` at the root
+    placement: Option<Coordinates>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // The moves that have no child yet
+    untried: Vec<Coordinates>,
+    visits: u64,
+    // The playouts won by the player who made the move that led here
+    wins: f64,
+}
+
+/// The positions searched so far, the root first.
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn new(board: &Board) -> Self {
+        Self {
+            nodes: vec![Node::new(board.clone(), None, None)],
+        }
+    }
+
+    /// Runs one playout: selection, expansion, simulation and backpropagation.
+    fn playout(&mut self, random: &mut Random) {
+        // Follow the best children down to a node that still has moves to try
+        let mut index = 0;
+        while self.nodes[index].untried.is_empty() && !self.nodes[index].children.is_empty() {
+            index = self.select_child(index);
+        }
+
+        // Give it a child for one of those moves, unless the game is over
+        if !self.nodes[index].untried.is_empty() {
+            let untried = &mut self.nodes[index].untried;
+            let placement = untried.swap_remove(random.below(untried.len()));
+            let board = self.nodes[index].board.make_move(placement);
+            self.nodes
+                .push(Node::new(board, Some(placement), Some(index)));
+            let child = self.nodes.len() - 1;
+            self.nodes[index].children.push(child);
+            index = child;
+        }
+
+        let rewards = simulate(&self.nodes[index].board, random);
+
+        // Credit every move on the way back up to the player who made it
+        let mut current = Some(index);
+        while let Some(node) = current {
+            self.nodes[node].visits += 1;
+            if let Some(parent) = self.nodes[node].parent {
+                let mover = self.nodes[parent].board.turn();
+                self.nodes[node].wins += rewards[mover.index()];
+            }
+            current = self.nodes[node].parent;
+        }
+    }
+
+    // Returns the child of `parent` with the best UCT value: its win rate, plus a
+    // bonus that grows the less it was visited compared to its parent.
+    fn select_child(&self, parent: usize) -> usize {
+        let log_visits = (self.nodes[parent].visits as f64).ln();
+        let uct = |child: usize| {
+            let node = &self.nodes[child];
+            let visits = node.visits as f64;
+            node.wins / visits + EXPLORATION * (log_visits / visits).sqrt()
+        };
+        self.nodes[parent]
+            .children
+            .iter()
+            .copied()
+            .reduce(|best, child| if uct(child) > uct(best) { child } else { best })
+            .expect("a node is only selected from when it has children")
+    }
+
+    // Returns the statistics of the moves of the root, in the order of the valid moves.
+    fn root_statistics(&self, board: &Board) -> Vec<MoveStatistics> {
+        board
+            .valid_moves()
+            .map(|placement| {
+                let child = self.nodes[0]
+                    .children
+                    .iter()
+                    .map(|child| &self.nodes[*child])
+                    .find(|child| child.placement == Some(placement));
+                MoveStatistics {
+                    placement,
+                    visits: child.map_or(0, |child| child.visits),
+                    wins: child.map_or(0.0, |child| child.wins),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Node {
+    fn new(board: Board, placement: Option<Coordinates>, parent: Option<usize>) -> Self {
+        let untried = if board.is_game_over() {
+            Vec::new()
+        } else {
+            board.get_valid_moves()
+        };
+        Self {
+            board,
+            placement,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+}
+
+/// Plays the game out from `board` with random moves, and returns the share of the
+/// win of every player, by `Player::index`.
+///
+/// Half of the moves start a chain reaction when one can, which keeps playouts
+/// closer to real games than picking every move at random.
+fn simulate(board: &Board, random: &mut Random) -> [f64; Player::MAX_PLAYERS] {
+    let mut board = board.clone();
+    for _ in 0..board.dimensions().area() * PLAYOUT_MOVES_PER_SQUARE {
+        if board.is_game_over() {
+            break;
+        }
+
+        let explosive = board
+            .valid_moves()
+            .filter(|placement| engine::is_explosive(&board, *placement))
+            .count();
+        let placement = if explosive > 0 && random.below(2) == 0 {
+            board
+                .valid_moves()
+                .filter(|placement| engine::is_explosive(&board, *placement))
+                .nth(random.below(explosive))
+        } else {
+            let count = board.valid_moves().count();
+            board.valid_moves().nth(random.below(count))
+        };
+        board = board.make_move(placement.expect("the player to move has a square"));
+    }
+
+    // A game that did not end is shared by the players holding the most squares
+    let mut rewards = [0.0; Player::MAX_PLAYERS];
+    let most_squares = board
+        .players()
+        .iter()
+        .map(|player| board.square_count(*player))
+        .max()
+        .unwrap_or(0);
+    let leaders: Vec<Player> = board
+        .players()
+        .iter()
+        .copied()
+        .filter(|player| board.square_count(*player) == most_squares)
+        .collect();
+    for leader in &leaders {
+        rewards[leader.index()] = 1.0 / leaders.len() as f64;
+    }
+    rewards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimensions::Dimensions;
+
+    fn setup_test_board() -> Board {
+        let dimensions = Dimensions::default();
+        Board::setup(
+            dimensions,
+            &[
+                Coordinates::new(1, 1, dimensions),
+                Coordinates::new(3, 3, dimensions),
+            ],
+        )
+    }
+
+    #[test]
+    fn finds_the_winning_move() {
+        let board: Board = "r3b1/.. r".parse().unwrap();
+        let result = monte_carlo_search(&board, SearchLimits::nodes(50), &StopHandle::new());
+
+        let winning_move = Coordinates::new(0, 0, board.dimensions());
+        assert_eq!(result.best_move(), winning_move);
+        let statistics = &result.moves()[0];
+        assert_eq!(statistics.placement(), winning_move);
+        assert_eq!(statistics.win_rate(), 1.0);
+    }
+
+    #[test]
+    fn statistics_add_up() {
+        let board = setup_test_board();
+        let result = monte_carlo_search(&board, SearchLimits::nodes(300), &StopHandle::new());
+
+        assert_eq!(result.playouts(), 300);
+        let moves: Vec<Coordinates> = result
+            .moves()
+            .iter()
+            .map(MoveStatistics::placement)
+            .collect();
+        assert_eq!(moves, board.get_valid_moves());
+        assert_eq!(
+            result
+                .moves()
+                .iter()
+                .map(MoveStatistics::visits)
+                .sum::<u64>(),
+            300
+        );
+        for statistics in result.moves() {
+            assert!(statistics.wins() <= statistics.visits() as f64);
+            assert!((0.0..=1.0).contains(&statistics.win_rate()));
+        }
+        let best = result
+            .moves()
+            .iter()
+            .find(|statistics| statistics.placement() == result.best_move())
+            .unwrap();
+        assert!(
+            result
+                .moves()
+                .iter()
+                .all(|statistics| statistics.visits() <= best.visits())
+        );
+    }
+
+    #[test]
+    fn playouts_are_reproducible() {
+        let board = setup_test_board();
+        let first = monte_carlo_search(&board, SearchLimits::nodes(200), &StopHandle::new());
+        let second = monte_carlo_search(&board, SearchLimits::nodes(200), &StopHandle::new());
+        assert_eq!(first.best_move(), second.best_move());
+        assert_eq!(first.moves(), second.moves());
+    }
+
+    #[test]
+    fn stops_when_asked() {
+        let board = setup_test_board();
+        let stop = StopHandle::new();
+        stop.stop();
+        let result = monte_carlo_search(&board, SearchLimits::infinite(), &stop);
+
+        assert_eq!(result.playouts(), 0);
+        assert_eq!(result.best_move(), board.get_valid_moves()[0]);
+        assert!(
+            result
+                .moves()
+                .iter()
+                .all(|statistics| statistics.visits() == 0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the game is over")]
+    fn finished_games() {
+        let board: Board = "r1../... r".parse().unwrap();
+        monte_carlo_search(&board, SearchLimits::nodes(10), &StopHandle::new());
+    }
+
+    #[test]
+    fn depth_and_threads_are_ignored() {
+        let board = setup_test_board();
+        let limits = SearchLimits::nodes(50);
+        let result = monte_carlo_search(&board, limits, &StopHandle::new());
+        let other = monte_carlo_search(
+            &board,
+            limits.with_depth(1).with_threads(4),
+            &StopHandle::new(),
+        );
+        assert_eq!(other.playouts(), 50);
+        assert_eq!(other.best_move(), result.best_move());
+        assert_eq!(other.moves(), result.moves());
+    }
+
+    #[test]
+    fn four_player_games() {
+        let dimensions = Dimensions::default();
+        let board = Board::setup(
+            dimensions,
+            &[
+                Coordinates::new(0, 0, dimensions),
+                Coordinates::new(0, 4, dimensions),
+                Coordinates::new(4, 0, dimensions),
+                Coordinates::new(4, 4, dimensions),
+            ],
+        );
+        let result = monte_carlo_search(&board, SearchLimits::nodes(100), &StopHandle::new());
+        assert!(board.get_valid_moves().contains(&result.best_move()));
+    }
+}
@@ -0,0 +1,111 @@
+//! Defines the `MoveError` type, returned when a move or setup breaks the rules.
+use std::{error::Error, fmt};
+
+use crate::{
+    coordinates::{Coordinates, algebraic_name},
+    dimensions::Dimensions,
+    player::Player,
+};
+
+/// The reasons a move or an initial placement can be rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveError {
+    /// The coordinates lie outside the board.
+    OutOfBounds {
+        row: usize,
+        column: usize,
+        dimensions: Dimensions,
+    },
+    /// The chosen square has no owner, so it cannot be incremented.
+    EmptySquare(Coordinates),
+    /// The chosen square belongs to another player.
+    NotYourSquare {
+        coordinates: Coordinates,
+        owner: Player,
+    },
+    /// The game has already been won, so no more moves can be made.
+    GameAlreadyOver,
+    /// Two players tried to start on the same square.
+    SameStartingSquare(Coordinates),
+    /// A game was set up with fewer than 2 or more than 4 players.
+    InvalidPlayerCount(usize),
+}
+
+/// A way to print to terminal why the move was rejected
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::OutOfBounds {
+                row,
+                column,
+                dimensions,
+            } => write!(
+                f,
+                "{} is outside the {dimensions} board",
+                algebraic_name(*row, *column)
+            ),
+            MoveError::EmptySquare(coordinates) => write!(f, "{coordinates} is an empty square"),
+            MoveError::NotYourSquare { coordinates, owner } => {
+                write!(f, "{coordinates} belongs to {owner}")
+            }
+            MoveError::GameAlreadyOver => write!(f, "the game is already over"),
+            MoveError::SameStartingSquare(coordinates) => {
+                write!(f, "two players cannot start on {coordinates}")
+            }
+            MoveError::InvalidPlayerCount(count) => write!(
+                f,
+                "a game needs between {} and {} players, not {count}",
+                Player::MIN_PLAYERS,
+                Player::MAX_PLAYERS
+            ),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_trait() {
+        let dimensions = Dimensions::default();
+        let coordinates = Coordinates::new(1, 2, dimensions);
+
+        assert_eq!(
+            MoveError::OutOfBounds {
+                row: 5,
+                column: 0,
+                dimensions
+            }
+            .to_string(),
+            "a6 is outside the 5x5 board"
+        );
+        assert_eq!(
+            MoveError::EmptySquare(coordinates).to_string(),
+            "c2 is an empty square"
+        );
+        assert_eq!(
+            MoveError::NotYourSquare {
+                coordinates,
+                owner: Player::Blue
+            }
+            .to_string(),
+            "c2 belongs to Blue"
+        );
+        assert_eq!(
+            MoveError::GameAlreadyOver.to_string(),
+            "the game is already over"
+        );
+        assert_eq!(
+            MoveError::SameStartingSquare(coordinates).to_string(),
+            "two players cannot start on c2"
+        );
+        assert_eq!(
+            MoveError::InvalidPlayerCount(5).to_string(),
+            "a game needs between 2 and 4 players, not 5"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Defines the `MoveTrace` type, an ordered record of everything that happened during a move.
+use crate::{coordinates::Coordinates, player::Player};
+
+/// A single step of a move's resolution.
+///
+/// Every event that belongs to a chain reaction carries the `wave` it happened in.
+/// The chosen square's increment is wave `0`, its pop is wave `1`, and any pop
+/// triggered by a square that popped in wave `n` happens in wave `n + 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveEvent {
+    /// The chosen square was incremented to `value`. Its `wave` is always `0`.
+    Increment {
+        coordinates: Coordinates,
+        value: u8,
+        wave: usize,
+    },
+    /// A square reached `4` and popped, becoming empty.
+    Pop {
+        coordinates: Coordinates,
+        wave: usize,
+    },
+    /// A neighbor of a popped square changed hands.
+    /// `previous_owner` is `None` when the neighbor was empty.
+    Capture {
+        coordinates: Coordinates,
+        previous_owner: Option<Player>,
+        wave: usize,
+    },
+    /// A neighbor of a popped square received `+1` and now holds `value`.
+    NeighborIncrement {
+        coordinates: Coordinates,
+        value: u8,
+        wave: usize,
+    },
+}
+
+/// The ordered list of events produced by a single move.
+///
+/// Replaying the events in order reproduces the move step by step, which is
+/// useful for animating a cascade or measuring how big a chain reaction was.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoveTrace {
+    events: Vec<MoveEvent>,
+}
+
+impl MoveTrace {
+    /// Creates an empty trace.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an event to the trace.
+    pub fn push(&mut self, event: MoveEvent) {
+        self.events.push(event);
+    }
+
+    /// Returns the events in the order they happened.
+    pub fn events(&self) -> &[MoveEvent] {
+        &self.events
+    }
+
+    /// Returns the number of squares that popped during the move.
+    pub fn pop_count(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|event| matches!(event, MoveEvent::Pop { .. }))
+            .count()
+    }
+
+    /// Returns the number of chain reaction waves, `0` if nothing popped.
+    pub fn wave_count(&self) -> usize {
+        self.events
+            .iter()
+            .filter_map(|event| match event {
+                MoveEvent::Pop { wave, .. } => Some(*wave),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimensions::Dimensions;
+
+    #[test]
+    fn empty_trace() {
+        let trace = MoveTrace::new();
+        assert!(trace.events().is_empty());
+        assert_eq!(trace.pop_count(), 0);
+        assert_eq!(trace.wave_count(), 0);
+    }
+
+    #[test]
+    fn counts_pops_and_waves() {
+        let dimensions = Dimensions::default();
+        let coordinates = Coordinates::new(0, 0, dimensions);
+        let mut trace = MoveTrace::new();
+
+        trace.push(MoveEvent::Increment {
+            coordinates,
+            value: 4,
+            wave: 0,
+        });
+        trace.push(MoveEvent::Pop {
+            coordinates,
+            wave: 1,
+        });
+        trace.push(MoveEvent::Pop {
+            coordinates: Coordinates::new(0, 1, dimensions),
+            wave: 2,
+        });
+
+        assert_eq!(trace.events().len(), 3);
+        assert_eq!(trace.pop_count(), 2);
+        assert_eq!(trace.wave_count(), 2);
+    }
+}
@@ -0,0 +1,548 @@
+//! Defines `NeuralEvaluator`, a small neural network that learns to evaluate
+//! positions from games, run on the CPU.
+use std::{error::Error, fmt, fs, io, path::Path, str::FromStr};
+
+use crate::{
+    board::Board, dimensions::Dimensions, engine::is_red_side, evaluator::Evaluator,
+    random::Random, score::Score, tuning::TrainingPosition,
+};
+
+/// An evaluator made of a neural network with one hidden layer.
+///
+/// The network sees each square as one of six inputs, set when the square belongs
+/// to the side to move or to the other side, with a value of 1, 2 or 3, and empty
+/// squares as none. The hidden layer is fully connected with ReLU activations, and
+/// the output is the log-odds of the side to move winning. Only the set inputs are
+/// added up, so evaluating a position costs a few additions per occupied square.
+///
+/// A network evaluates boards of the size it was created for. Its weights are saved
+/// as text, a `network ROWSxCOLUMNS HIDDEN` header followed by the weights, in the
+/// format of `Display`.
+///
+/// # Example
+///
+/// ```
+/// use color_wars::prelude::*;
+///
+/// let dimensions = Dimensions::new(3, 3);
+/// let games = self_play_games(
+///     dimensions,
+///     2,
+///     4,
+///     SearchLimits::depth(1),
+///     &StandardEvaluator::default(),
+///     3,
+/// );
+/// let positions: Vec<TrainingPosition> = games.iter().flat_map(training_positions).collect();
+///
+/// let mut network = NeuralEvaluator::new(dimensions, 8, 1);
+/// network.train(&positions, 10, 0.01);
+///
+/// let result = search_with_evaluator(
+///     &games[0].boards()[0],
+///     SearchLimits::depth(2),
+///     &StopHandle::new(),
+///     &mut TranspositionTable::default(),
+///     &network,
+///     |_| {},
+/// );
+/// assert_eq!(result.depth(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "NeuralEvaluatorFields", into = "NeuralEvaluatorFields")
+)]
+pub struct NeuralEvaluator {
+    dimensions: Dimensions,
+    hidden: usize,
+    // The weights from each input to every hidden neuron, input after input
+    input_weights: Vec<f32>,
+    hidden_biases: Vec<f32>,
+    output_weights: Vec<f32>,
+    output_bias: f32,
+}
+
+impl NeuralEvaluator {
+    /// The largest number of hidden neurons, so evaluating a position keeps its hidden
+    /// layer on the stack instead of allocating it.
+    pub const MAX_HIDDEN: usize = 256;
+
+    /// The number of inputs per square.
+    const SQUARE_INPUTS: usize = 6;
+
+    /// How many points of evaluation the log-odds of winning are worth. A network
+    /// that expects to win 3 games out of 4 scores about 110.
+    const EVALUATION_SCALE: f32 = 100.0;
+
+    /// Creates a network for boards of `dimensions` with `hidden` hidden neurons,
+    /// with small random weights drawn from `seed`.
+    ///
+    /// # Panics
+    /// Panics if `hidden` is `0` or greater than `MAX_HIDDEN`.
+    pub fn new(dimensions: Dimensions, hidden: usize, seed: u64) -> Self {
+        assert!(
+            (1..=Self::MAX_HIDDEN).contains(&hidden),
+            "a network has 1 to {} hidden neurons, not {hidden}",
+            Self::MAX_HIDDEN
+        );
+        let mut random = Random::new(seed);
+        let mut weights = |count: usize, range: f32| -> Vec<f32> {
+            (0..count)
+                .map(|_| (random.below(2001) as f32 / 1000.0 - 1.0) * range)
+                .collect()
+        };
+
+        let inputs = dimensions.area() * Self::SQUARE_INPUTS;
+        let input_range = 1.0 / (dimensions.area() as f32).sqrt();
+        let output_range = 1.0 / (hidden as f32).sqrt();
+        Self {
+            dimensions,
+            hidden,
+            input_weights: weights(inputs * hidden, input_range),
+            hidden_biases: vec![0.0; hidden],
+            output_weights: weights(hidden, output_range),
+            output_bias: 0.0,
+        }
+    }
+
+    /// Returns the size of the boards the network evaluates.
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    /// Returns the number of hidden neurons.
+    pub fn hidden(&self) -> usize {
+        self.hidden
+    }
+
+    /// Trains the network on `positions` for `epochs` passes with stochastic gradient
+    /// descent, and returns the mean loss of the last pass.
+    ///
+    /// The network learns to predict the result of each position's game for the side
+    /// to move, by lowering the cross-entropy of its predictions. The positions are
+    /// shuffled before every pass, the same way on every call.
+    ///
+    /// # Panics
+    /// Panics if a position is not on a board of the network's dimensions.
+    pub fn train(
+        &mut self,
+        positions: &[TrainingPosition],
+        epochs: usize,
+        learning_rate: f32,
+    ) -> f64 {
+        let mut order: Vec<usize> = (0..positions.len()).collect();
+        let mut random = Random::new(positions.len() as u64);
+        let mut loss = 0.0;
+        for _ in 0..epochs {
+            for index in (1..order.len()).rev() {
+                order.swap(index, random.below(index + 1));
+            }
+
+            loss = 0.0;
+            for &index in &order {
+                let position = &positions[index];
+                let board = position.board();
+                // The games are scored for Red, the network for the side to move
+                let target = if is_red_side(board.turn()) {
+                    position.result()
+                } else {
+                    1.0 - position.result()
+                } as f32;
+                loss += f64::from(self.train_position(board, target, learning_rate));
+            }
+            loss /= positions.len().max(1) as f64;
+        }
+        loss
+    }
+
+    /// Reads a network saved with `save`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, or with the kind
+    /// `io::ErrorKind::InvalidData` if it does not hold a network.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// Writes the network to the file at `path`, replacing it.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    // Creates a network from its weights, checking there are as many as its
+    // dimensions and hidden neurons need.
+    fn from_parts(
+        dimensions: Dimensions,
+        hidden: usize,
+        input_weights: Vec<f32>,
+        hidden_biases: Vec<f32>,
+        output_weights: Vec<f32>,
+        output_bias: f32,
+    ) -> Result<Self, ParseNeuralEvaluatorError> {
+        if !(1..=Self::MAX_HIDDEN).contains(&hidden) {
+            return Err(ParseNeuralEvaluatorError(format!(
+                "expected 1 to {} hidden neurons, found {hidden}",
+                Self::MAX_HIDDEN
+            )));
+        }
+        let input_count = dimensions.area() * Self::SQUARE_INPUTS * hidden;
+        for (name, weights, expected) in [
+            ("input weights", &input_weights, input_count),
+            ("hidden biases", &hidden_biases, hidden),
+            ("output weights", &output_weights, hidden),
+        ] {
+            if weights.len() != expected {
+                return Err(ParseNeuralEvaluatorError(format!(
+                    "expected {expected} {name}, found {}",
+                    weights.len()
+                )));
+            }
+        }
+
+        Ok(Self {
+            dimensions,
+            hidden,
+            input_weights,
+            hidden_biases,
+            output_weights,
+            output_bias,
+        })
+    }
+
+    // Calls `on_input` with the index of every set input of `board`.
+    fn inputs(&self, board: &Board, mut on_input: impl FnMut(usize)) {
+        assert_eq!(
+            board.dimensions(),
+            self.dimensions,
+            "the network evaluates boards of another size"
+        );
+        let side = is_red_side(board.turn());
+        for (index, square) in board.grid().rows().flatten().enumerate() {
+            if let Some(owner) = square.owner() {
+                let value = usize::from(square.value().clamp(1, 3)) - 1;
+                let relation = if is_red_side(owner) == side { 0 } else { 3 };
+                on_input(index * Self::SQUARE_INPUTS + relation + value);
+            }
+        }
+    }
+
+    // Computes the hidden layer of `board` into `hidden`, before the activation, and
+    // returns the output, the log-odds of the side to move winning.
+    fn forward(&self, board: &Board, hidden: &mut [f32]) -> f32 {
+        hidden.copy_from_slice(&self.hidden_biases);
+        self.inputs(board, |input| {
+            let weights = &self.input_weights[input * self.hidden..][..self.hidden];
+            for (neuron, weight) in hidden.iter_mut().zip(weights) {
+                *neuron += weight;
+            }
+        });
+        hidden
+            .iter()
+            .zip(&self.output_weights)
+            .map(|(neuron, weight)| neuron.max(0.0) * weight)
+            .sum::<f32>()
+            + self.output_bias
+    }
+
+    // Takes one gradient step towards predicting `target` for `board`, and returns
+    // the cross-entropy of the prediction before the step.
+    fn train_position(&mut self, board: &Board, target: f32, learning_rate: f32) -> f32 {
+        let mut hidden = [0.0; Self::MAX_HIDDEN];
+        let hidden = &mut hidden[..self.hidden];
+        let output = self.forward(board, hidden);
+        let prediction = 1.0 / (1.0 + (-output).exp());
+        let loss = -(target * prediction.max(f32::EPSILON).ln()
+            + (1.0 - target) * (1.0 - prediction).max(f32::EPSILON).ln());
+
+        // The gradient of the cross-entropy with respect to the output
+        let gradient = prediction - target;
+        let mut hidden_gradients = [0.0; Self::MAX_HIDDEN];
+        let hidden_gradients = &mut hidden_gradients[..self.hidden];
+        for ((neuron, weight), hidden_gradient) in hidden
+            .iter()
+            .zip(&mut self.output_weights)
+            .zip(hidden_gradients.iter_mut())
+        {
+            if *neuron > 0.0 {
+                *hidden_gradient = gradient * *weight;
+            }
+            *weight -= learning_rate * gradient * neuron.max(0.0);
+        }
+        self.output_bias -= learning_rate * gradient;
+
+        for (bias, hidden_gradient) in self.hidden_biases.iter_mut().zip(hidden_gradients.iter()) {
+            *bias -= learning_rate * hidden_gradient;
+        }
+        let mut set_inputs = Vec::new();
+        self.inputs(board, |input| set_inputs.push(input));
+        for input in set_inputs {
+            let weights = &mut self.input_weights[input * self.hidden..][..self.hidden];
+            for (weight, hidden_gradient) in weights.iter_mut().zip(hidden_gradients.iter()) {
+                *weight -= learning_rate * hidden_gradient;
+            }
+        }
+        loss
+    }
+}
+
+/// Scores the position by the network's log-odds of the side to move winning.
+///
+/// # Panics
+/// Panics if the board is not of the network's dimensions.
+impl Evaluator for NeuralEvaluator {
+    fn evaluate(&self, board: &Board) -> Score {
+        let mut hidden = [0.0; Self::MAX_HIDDEN];
+        let output = self.forward(board, &mut hidden[..self.hidden]);
+        Score::evaluation((output * Self::EVALUATION_SCALE).round() as i32)
+    }
+}
+
+/// Formats the network as its header line, then one line of input weights per input,
+/// the hidden biases, the output weights and the output bias.
+impl fmt::Display for NeuralEvaluator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = |weights: &[f32]| {
+            weights
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        writeln!(f, "network {} {}", self.dimensions, self.hidden)?;
+        for weights in self.input_weights.chunks(self.hidden) {
+            writeln!(f, "{}", line(weights))?;
+        }
+        writeln!(f, "{}", line(&self.hidden_biases))?;
+        writeln!(f, "{}", line(&self.output_weights))?;
+        writeln!(f, "{}", self.output_bias)
+    }
+}
+
+/// The error returned when text does not hold a network.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseNeuralEvaluatorError(String);
+
+/// A way to print to terminal why the network could not be parsed
+impl fmt::Display for ParseNeuralEvaluatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid network: {}", self.0)
+    }
+}
+
+impl Error for ParseNeuralEvaluatorError {}
+
+/// Parses a network written in the format of `Display`.
+impl FromStr for NeuralEvaluator {
+    type Err = ParseNeuralEvaluatorError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = |reason: String| ParseNeuralEvaluatorError(reason);
+
+        let mut tokens = text.split_whitespace();
+        if tokens.next() != Some("network") {
+            return Err(error(String::from("expected a `network` header")));
+        }
+        let dimensions: Dimensions = tokens
+            .next()
+            .unwrap_or_default()
+            .parse()
+            .map_err(|parse_error| error(format!("{parse_error}")))?;
+        let hidden = tokens
+            .next()
+            .and_then(|hidden| hidden.parse::<usize>().ok())
+            .filter(|hidden| (1..=Self::MAX_HIDDEN).contains(hidden))
+            .ok_or_else(|| error(format!("expected 1 to {} hidden neurons", Self::MAX_HIDDEN)))?;
+
+        let weights = tokens
+            .map(|token| {
+                token
+                    .parse::<f32>()
+                    .map_err(|_| error(format!("`{token}` is not a weight")))
+            })
+            .collect::<Result<Vec<f32>, _>>()?;
+        let input_count = dimensions.area() * Self::SQUARE_INPUTS * hidden;
+        let expected = input_count + 2 * hidden + 1;
+        if weights.len() != expected {
+            return Err(error(format!(
+                "expected {expected} weights, found {}",
+                weights.len()
+            )));
+        }
+
+        let (input_weights, rest) = weights.split_at(input_count);
+        let (hidden_biases, rest) = rest.split_at(hidden);
+        let (output_weights, output_bias) = rest.split_at(hidden);
+        Self::from_parts(
+            dimensions,
+            hidden,
+            input_weights.to_vec(),
+            hidden_biases.to_vec(),
+            output_weights.to_vec(),
+            output_bias[0],
+        )
+    }
+}
+
+// The serialized form of a `NeuralEvaluator`, checked before it becomes one.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct NeuralEvaluatorFields {
+    dimensions: Dimensions,
+    hidden: usize,
+    input_weights: Vec<f32>,
+    hidden_biases: Vec<f32>,
+    output_weights: Vec<f32>,
+    output_bias: f32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<NeuralEvaluatorFields> for NeuralEvaluator {
+    type Error = ParseNeuralEvaluatorError;
+
+    fn try_from(fields: NeuralEvaluatorFields) -> Result<Self, Self::Error> {
+        Self::from_parts(
+            fields.dimensions,
+            fields.hidden,
+            fields.input_weights,
+            fields.hidden_biases,
+            fields.output_weights,
+            fields.output_bias,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<NeuralEvaluator> for NeuralEvaluatorFields {
+    fn from(network: NeuralEvaluator) -> Self {
+        NeuralEvaluatorFields {
+            dimensions: network.dimensions,
+            hidden: network.hidden,
+            input_weights: network.input_weights,
+            hidden_biases: network.hidden_biases,
+            output_weights: network.output_weights,
+            output_bias: network.output_bias,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(notation: &str, result: f64) -> TrainingPosition {
+        TrainingPosition::new(notation.parse().unwrap(), result)
+    }
+
+    #[test]
+    fn evaluates_for_the_side_to_move() {
+        let network = NeuralEvaluator::new(Dimensions::new(2, 3), 4, 9);
+        // The same squares, seen from the side to move, give the same inputs
+        let red: Board = "r2b1./... r".parse().unwrap();
+        let blue: Board = "b2r1./... b".parse().unwrap();
+        assert_eq!(network.evaluate(&red), network.evaluate(&blue));
+    }
+
+    #[test]
+    fn training_lowers_the_loss() {
+        // The player holding the larger square wins
+        let positions = [
+            position("r3b1./... r", 1.0),
+            position("r1b3./... r", 0.0),
+            position("r1b3./... b", 0.0),
+            position("r3b1./... b", 1.0),
+            position("r2../b1.. r", 1.0),
+            position("r1../b2.. r", 0.0),
+        ];
+        let mut network = NeuralEvaluator::new(Dimensions::new(2, 3), 8, 4);
+        let first_loss = network.train(&positions, 1, 0.1);
+        let last_loss = network.train(&positions, 200, 0.1);
+        assert!(last_loss < first_loss / 2.0, "{first_loss} -> {last_loss}");
+
+        let winning: Board = "r3b1./... r".parse().unwrap();
+        let losing: Board = "r1b3./... r".parse().unwrap();
+        assert!(network.evaluate(&winning) > Score::evaluation(0));
+        assert!(network.evaluate(&losing) < Score::evaluation(0));
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let network = NeuralEvaluator::new(Dimensions::new(2, 2), 3, 1);
+        let text = network.to_string();
+        assert!(text.starts_with("network 2x2 3\n"));
+        assert_eq!(text.lines().count(), 1 + 24 + 3);
+        assert_eq!(text.parse(), Ok(network));
+    }
+
+    #[test]
+    fn invalid_networks() {
+        let network = NeuralEvaluator::new(Dimensions::new(2, 2), 3, 1).to_string();
+        assert!("".parse::<NeuralEvaluator>().is_err());
+        assert!("network 2x2 0".parse::<NeuralEvaluator>().is_err());
+        assert!("network 2x2 257".parse::<NeuralEvaluator>().is_err());
+        assert!(
+            network
+                .replace("network 2x2", "network 3x3")
+                .parse::<NeuralEvaluator>()
+                .is_err()
+        );
+        assert!(format!("{network} 0.5").parse::<NeuralEvaluator>().is_err());
+        assert!(
+            network
+                .replacen('\n', "\nx ", 1)
+                .parse::<NeuralEvaluator>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("color_wars_network_{}.txt", std::process::id()));
+        let network = NeuralEvaluator::new(Dimensions::new(3, 3), 5, 2);
+        network.save(&path).unwrap();
+        assert_eq!(NeuralEvaluator::load(&path).unwrap(), network);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_is_checked() {
+        let network = NeuralEvaluator::new(Dimensions::new(2, 2), 3, 1);
+        let json = serde_json::to_string(&network).unwrap();
+        assert_eq!(
+            serde_json::from_str::<NeuralEvaluator>(&json).unwrap(),
+            network
+        );
+
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["output_weights"] = serde_json::json!([0.5]);
+        let error = serde_json::from_value::<NeuralEvaluator>(value).unwrap_err();
+        assert!(error.to_string().contains("output weights"), "{error}");
+
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["hidden"] = serde_json::json!(4);
+        assert!(serde_json::from_value::<NeuralEvaluator>(value).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "hidden neurons")]
+    fn too_many_hidden_neurons() {
+        NeuralEvaluator::new(Dimensions::new(2, 2), NeuralEvaluator::MAX_HIDDEN + 1, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn other_board_sizes() {
+        let network = NeuralEvaluator::new(Dimensions::new(3, 3), 2, 0);
+        network.evaluate(&"r1b1/.. r".parse().unwrap());
+    }
+}
@@ -0,0 +1,403 @@
+//! Reads and writes the textual position notation used to save and share boards.
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::{
+    board::Board, coordinates::algebraic_name, dimensions::Dimensions, player::Player,
+    square::Square,
+};
+
+/// The reasons a position string can fail to parse.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NotationError {
+    /// The notation does not have 2 or 3 space separated fields.
+    WrongFieldCount(usize),
+    /// A row does not have as many squares as the first row.
+    RaggedRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The board has fewer than 2 rows or columns.
+    TooSmall { rows: usize, columns: usize },
+    /// A square is neither `.` nor a player letter followed by a value from `1` to `3`.
+    InvalidSquare {
+        row: usize,
+        column: usize,
+        text: String,
+    },
+    /// The side to move is not a player letter.
+    InvalidTurn(String),
+    /// The player count is not a number from `2` to `4`.
+    InvalidPlayerCount(String),
+    /// A square or the side to move belongs to a player outside the game.
+    PlayerNotInGame(Player),
+    /// The side to move has no squares left while another player has.
+    EliminatedTurn(Player),
+}
+
+/// A way to print to terminal why the notation could not be parsed
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::WrongFieldCount(count) => write!(
+                f,
+                "expected `<grid> <turn> [<players>]`, found {count} fields"
+            ),
+            NotationError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(f, "row {row} has {found} squares, expected {expected}"),
+            NotationError::TooSmall { rows, columns } => {
+                write!(f, "a {rows}x{columns} board is too small")
+            }
+            NotationError::InvalidSquare { row, column, text } => {
+                write!(
+                    f,
+                    "invalid square `{text}` at {}",
+                    algebraic_name(*row, *column)
+                )
+            }
+            NotationError::InvalidTurn(text) => write!(f, "invalid side to move `{text}`"),
+            NotationError::InvalidPlayerCount(text) => {
+                write!(f, "invalid player count `{text}`")
+            }
+            NotationError::PlayerNotInGame(player) => {
+                write!(f, "{player} does not take part in the game")
+            }
+            NotationError::EliminatedTurn(player) => {
+                write!(f, "{player} has no squares left to play")
+            }
+        }
+    }
+}
+
+impl Error for NotationError {}
+
+impl Board {
+    /// Writes the board as a compact position string, which can be read back with `parse`.
+    ///
+    /// A position is written as `<grid> <turn> [<players>]`, for example
+    /// `r3..../...../...../...../....b3 r` for a standard setup.
+    ///
+    /// - `<grid>` lists the rows from top to bottom, separated by `/`. Each square is
+    ///   either `.` when empty, or the owner's letter followed by its value from `1` to `3`.
+    ///   The letters are `r` (Red), `b` (Blue), `g` (Green) and `y` (Yellow). Every row
+    ///   must have the same number of squares, and the board must be at least `2x2`.
+    /// - `<turn>` is the letter of the player to move.
+    /// - `<players>` is the number of players in the game, from `2` to `4`. It is
+    ///   omitted when it is the smallest count that includes the player to move and every
+    ///   player on the board.
+    pub fn to_notation(&self) -> String {
+        let rows: Vec<String> = self
+            .grid()
+            .rows()
+            .map(|row| row.iter().map(|square| square_notation(*square)).collect())
+            .collect();
+
+        let mut notation = format!("{} {}", rows.join("/"), player_letter(self.turn()));
+        if self.players().len() != default_player_count(self) {
+            notation = format!("{notation} {}", self.players().len());
+        }
+        notation
+    }
+}
+
+/// Parses a position written in the notation described in [`Board::to_notation`].
+impl FromStr for Board {
+    type Err = NotationError;
+
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = notation.split_whitespace().collect();
+        if !(2..=3).contains(&fields.len()) {
+            return Err(NotationError::WrongFieldCount(fields.len()));
+        }
+
+        let rows = parse_grid(fields[0])?;
+        let turn = parse_player_letter(fields[1])
+            .ok_or_else(|| NotationError::InvalidTurn(fields[1].to_string()))?;
+
+        let highest_player = rows
+            .iter()
+            .flatten()
+            .filter_map(|square| square.owner())
+            .chain([turn])
+            .map(|player| player.index() + 1)
+            .max()
+            .unwrap_or(0);
+
+        let player_count = match fields.get(2) {
+            Some(text) => text
+                .parse::<usize>()
+                .ok()
+                .filter(|count| (Player::MIN_PLAYERS..=Player::MAX_PLAYERS).contains(count))
+                .ok_or_else(|| NotationError::InvalidPlayerCount(text.to_string()))?,
+            None => highest_player.max(Player::MIN_PLAYERS),
+        };
+        if highest_player > player_count {
+            return Err(NotationError::PlayerNotInGame(
+                Player::ALL[highest_player - 1],
+            ));
+        }
+
+        let dimensions = Dimensions::new(rows.len(), rows[0].len());
+        let mut board = Board::new(dimensions, player_count, turn);
+        for (coordinates, square) in dimensions.coordinates().zip(rows.into_iter().flatten()) {
+            board.set_square(coordinates, square);
+        }
+        if board.is_turn_eliminated() {
+            return Err(NotationError::EliminatedTurn(turn));
+        }
+
+        Ok(board)
+    }
+}
+
+// The player count a parser assumes when the notation leaves it out.
+fn default_player_count(board: &Board) -> usize {
+    board
+        .players()
+        .iter()
+        .filter(|player| **player == board.turn() || !board.is_eliminated(**player))
+        .map(|player| player.index() + 1)
+        .max()
+        .unwrap_or(0)
+        .max(Player::MIN_PLAYERS)
+}
+
+// Parses the `/` separated rows, checking the board is rectangular and large enough.
+fn parse_grid(text: &str) -> Result<Vec<Vec<Square>>, NotationError> {
+    let mut rows = Vec::new();
+    for (row_index, row_text) in text.split('/').enumerate() {
+        let row = parse_row(row_index, row_text)?;
+        if let Some(first_row) = rows.first().map(Vec::len)
+            && row.len() != first_row
+        {
+            return Err(NotationError::RaggedRow {
+                row: row_index,
+                expected: first_row,
+                found: row.len(),
+            });
+        }
+        rows.push(row);
+    }
+
+    let columns = rows[0].len();
+    if rows.len() < Dimensions::MIN_SIZE || columns < Dimensions::MIN_SIZE {
+        return Err(NotationError::TooSmall {
+            rows: rows.len(),
+            columns,
+        });
+    }
+    Ok(rows)
+}
+
+// Parses the squares of a single row.
+fn parse_row(row: usize, text: &str) -> Result<Vec<Square>, NotationError> {
+    let mut squares = Vec::new();
+    let mut characters = text.chars();
+
+    while let Some(character) = characters.next() {
+        let column = squares.len();
+        if character == '.' {
+            squares.push(Square::empty());
+            continue;
+        }
+
+        let value = characters.next();
+        let square = parse_player_letter(&character.to_string()).and_then(|owner| {
+            value
+                .and_then(|value| value.to_digit(10))
+                .filter(|value| (1..=3).contains(value))
+                .map(|value| Square::occupied(owner, value as u8))
+        });
+
+        match square {
+            Some(square) => squares.push(square),
+            None => {
+                return Err(NotationError::InvalidSquare {
+                    row,
+                    column,
+                    text: std::iter::once(character).chain(value).collect(),
+                });
+            }
+        }
+    }
+    Ok(squares)
+}
+
+// Writes a single square as `.` or its owner's letter followed by its value.
+fn square_notation(square: Square) -> String {
+    match square.owner() {
+        Some(owner) => format!("{}{}", player_letter(owner), square.value()),
+        None => String::from("."),
+    }
+}
+
+fn player_letter(player: Player) -> char {
+    match player {
+        Player::Red => 'r',
+        Player::Blue => 'b',
+        Player::Green => 'g',
+        Player::Yellow => 'y',
+    }
+}
+
+fn parse_player_letter(text: &str) -> Option<Player> {
+    match text {
+        "r" => Some(Player::Red),
+        "b" => Some(Player::Blue),
+        "g" => Some(Player::Green),
+        "y" => Some(Player::Yellow),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinates::Coordinates;
+
+    const STARTING_POSITION: &str = "r3..../...../...../...../....b3 r";
+
+    // Helper function to create a board for testing
+    fn setup_test_board() -> Board {
+        let dimensions = Dimensions::default();
+        Board::setup(
+            dimensions,
+            &[
+                Coordinates::new(0, 0, dimensions),
+                Coordinates::new(4, 4, dimensions),
+            ],
+        )
+    }
+
+    #[test]
+    fn to_notation_starting_position() {
+        assert_eq!(setup_test_board().to_notation(), STARTING_POSITION);
+    }
+
+    #[test]
+    fn parse_starting_position() {
+        assert_eq!(STARTING_POSITION.parse::<Board>(), Ok(setup_test_board()));
+    }
+
+    #[test]
+    fn round_trip_after_moves() {
+        let mut board = setup_test_board();
+        for _ in 0..6 {
+            board = board.make_move(board.get_valid_moves()[0]);
+            assert_eq!(board.to_notation().parse::<Board>(), Ok(board.clone()));
+        }
+        assert_eq!(board.to_notation(), ".r3.../r1..../...../....b3/...b1. r");
+    }
+
+    #[test]
+    fn round_trip_non_square_multiplayer() {
+        let dimensions = Dimensions::new(2, 3);
+        let board = Board::setup(
+            dimensions,
+            &[
+                Coordinates::new(0, 0, dimensions),
+                Coordinates::new(1, 2, dimensions),
+                Coordinates::new(0, 2, dimensions),
+            ],
+        );
+
+        assert_eq!(board.to_notation(), "r3.g3/..b3 r");
+        assert_eq!(board.to_notation().parse::<Board>(), Ok(board));
+    }
+
+    #[test]
+    fn explicit_player_count() {
+        let board: Board = "r3./.b3 b 4".parse().unwrap();
+        assert_eq!(board.players().len(), 4);
+        assert_eq!(board.turn(), Player::Blue);
+        assert_eq!(board.to_notation(), "r3./.b3 b 4");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |text: &str| text.parse::<Board>().unwrap_err();
+
+        assert_eq!(error("r3./.b3"), NotationError::WrongFieldCount(1));
+        assert_eq!(error("r3./.b3 r 2 x"), NotationError::WrongFieldCount(4));
+        assert_eq!(
+            error("r3../.b3 r"),
+            NotationError::RaggedRow {
+                row: 1,
+                expected: 3,
+                found: 2
+            }
+        );
+        assert_eq!(
+            error("r3b3 r"),
+            NotationError::TooSmall {
+                rows: 1,
+                columns: 2
+            }
+        );
+        assert_eq!(
+            error("r3./.b4 r"),
+            NotationError::InvalidSquare {
+                row: 1,
+                column: 1,
+                text: String::from("b4")
+            }
+        );
+        assert_eq!(
+            error("r3./x. r"),
+            NotationError::InvalidSquare {
+                row: 1,
+                column: 0,
+                text: String::from("x.")
+            }
+        );
+        assert_eq!(
+            error("r3./.b r"),
+            NotationError::InvalidSquare {
+                row: 1,
+                column: 1,
+                text: String::from("b")
+            }
+        );
+        assert_eq!(
+            error("r3./.b3 red"),
+            NotationError::InvalidTurn("red".into())
+        );
+        assert_eq!(
+            error("r3./.b3 r 5"),
+            NotationError::InvalidPlayerCount("5".into())
+        );
+        assert_eq!(
+            error("r3./.g3 r 2"),
+            NotationError::PlayerNotInGame(Player::Green)
+        );
+        assert_eq!(
+            error("r1../... b"),
+            NotationError::EliminatedTurn(Player::Blue)
+        );
+        assert_eq!(
+            error("r1./.b1 g 3"),
+            NotationError::EliminatedTurn(Player::Green)
+        );
+    }
+
+    #[test]
+    fn display_trait() {
+        assert_eq!(
+            NotationError::InvalidSquare {
+                row: 1,
+                column: 2,
+                text: String::from("q1")
+            }
+            .to_string(),
+            "invalid square `q1` at c2"
+        );
+        assert_eq!(
+            NotationError::PlayerNotInGame(Player::Yellow).to_string(),
+            "Yellow does not take part in the game"
+        );
+    }
+}
@@ -1,26 +1,70 @@
-//! Defines the `Player` type, representing one of the two participants in the game.
+//! Defines the `Player` type, representing one of the participants in the game.
 use std::fmt;
 
-/// Represents one of the two players in the game.
+/// Represents one of the up to four players in the game.
+///
+/// Players always take turns in declaration order: Red, Blue, Green and then Yellow.
+/// A game with `n` players uses the first `n` of them.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Player {
-    /// The Red player.
+    /// The Red player, who always moves first.
     Red,
     /// The Blue player.
     Blue,
+    /// The Green player, only present in games with 3 or more players.
+    Green,
+    /// The Yellow player, only present in 4-player games.
+    Yellow,
 }
 
 impl Player {
-    /// Returns the opposing player.
+    /// Every player, in turn order.
+    pub const ALL: [Player; 4] = [Player::Red, Player::Blue, Player::Green, Player::Yellow];
+
+    /// The smallest number of players a game can have.
+    pub const MIN_PLAYERS: usize = 2;
+
+    /// The largest number of players a game can have.
+    pub const MAX_PLAYERS: usize = 4;
+
+    /// Returns the zero-based position of the player in the turn order.
+    pub fn index(&self) -> usize {
+        match self {
+            Player::Red => 0,
+            Player::Blue => 1,
+            Player::Green => 2,
+            Player::Yellow => 3,
+        }
+    }
+
+    /// Returns the player who comes after this one in a game of `player_count` players.
+    ///
+    /// This does not know which players have been eliminated; see
+    /// [`Board::turn`](crate::board::Board::turn) for the rotation that skips them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `player_count` is outside `MIN_PLAYERS..=MAX_PLAYERS`.
+    #[must_use]
+    pub fn next(&self, player_count: usize) -> Player {
+        assert!((Self::MIN_PLAYERS..=Self::MAX_PLAYERS).contains(&player_count));
+        Self::ALL[(self.index() + 1) % player_count]
+    }
+
+    /// Returns the opposing player in a two-player game.
     ///
     /// This is useful for switching turns or checking for wins where the
-    /// context needs to be inverted.
+    /// context needs to be inverted. Only Red and Blue take part in a two-player
+    /// game, so debug builds panic when called on Green or Yellow.
     #[must_use]
+    #[deprecated(note = "games can have more than two players, use `next` instead")]
     pub fn opponent(&self) -> Player {
-        match self {
-            Player::Red => Player::Blue,
-            Player::Blue => Player::Red,
-        }
+        debug_assert!(
+            self.index() < 2,
+            "{self} does not take part in a two-player game"
+        );
+        self.next(2)
     }
 }
 
@@ -30,6 +74,8 @@ impl fmt::Display for Player {
         match self {
             Player::Red => write!(f, "Red"),
             Player::Blue => write!(f, "Blue"),
+            Player::Green => write!(f, "Green"),
+            Player::Yellow => write!(f, "Yellow"),
         }
     }
 }
@@ -47,23 +93,65 @@ mod tests {
     #[test]
     fn inequality() {
         assert_ne!(Player::Red, Player::Blue);
+        assert_ne!(Player::Green, Player::Yellow);
     }
 
     #[test]
+    fn index_matches_turn_order() {
+        for (index, player) in Player::ALL.iter().enumerate() {
+            assert_eq!(player.index(), index);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
     fn opponent() {
         assert_eq!(Player::Red.opponent(), Player::Blue);
         assert_eq!(Player::Blue.opponent(), Player::Red);
     }
 
     #[test]
+    #[allow(deprecated)]
     fn double_opponent() {
         assert_eq!(Player::Red.opponent().opponent(), Player::Red);
         assert_eq!(Player::Blue.opponent().opponent(), Player::Blue);
     }
 
+    #[test]
+    #[allow(deprecated)]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "two-player game")]
+    fn opponent_of_green() {
+        let _ = Player::Green.opponent();
+    }
+
+    #[test]
+    fn next_two_players() {
+        assert_eq!(Player::Red.next(2), Player::Blue);
+        assert_eq!(Player::Blue.next(2), Player::Red);
+        assert_eq!(Player::Red.next(2).next(2), Player::Red);
+    }
+
+    #[test]
+    fn next_four_players() {
+        assert_eq!(Player::Red.next(4), Player::Blue);
+        assert_eq!(Player::Blue.next(4), Player::Green);
+        assert_eq!(Player::Green.next(4), Player::Yellow);
+        assert_eq!(Player::Yellow.next(4), Player::Red);
+        assert_eq!(Player::Green.next(3), Player::Red);
+    }
+
+    #[test]
+    #[should_panic]
+    fn next_invalid_player_count() {
+        let _ = Player::Red.next(5);
+    }
+
     #[test]
     fn display_trait() {
         assert_eq!(format!("{}", Player::Red), "Red");
         assert_eq!(Player::Blue.to_string(), "Blue");
+        assert_eq!(Player::Green.to_string(), "Green");
+        assert_eq!(Player::Yellow.to_string(), "Yellow");
     }
 }
@@ -0,0 +1,693 @@
+//! Defines the `GameRecord` type and the plain-text format used to store whole games.
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::{
+    coordinates::Coordinates,
+    dimensions::Dimensions,
+    game::{Game, GameResult},
+    move_error::MoveError,
+    player::Player,
+};
+
+/// The only rule variant currently implemented, the rules described in the README.
+pub const STANDARD_VARIANT: &str = "Standard";
+
+/// The reasons a game record can fail to be read or replayed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum RecordError {
+    /// A header line is not written as `[Tag "value"]`.
+    MalformedTag { line: usize },
+    /// A tag every record needs is missing.
+    MissingTag(&'static str),
+    /// A tag's value could not be understood.
+    InvalidTag { tag: String, value: String },
+    /// A token in the move list is not a move.
+    InvalidMove { number: usize, text: String },
+    /// The record uses a rule variant this crate does not implement.
+    UnsupportedVariant(String),
+    /// The starting placements are not a valid setup.
+    InvalidSetup(MoveError),
+    /// A move is not legal in the position it was played in.
+    /// Moves are numbered from 1.
+    IllegalMove { number: usize, error: MoveError },
+    /// Replaying the moves does not lead to the recorded result.
+    ResultMismatch {
+        recorded: GameResult,
+        replayed: GameResult,
+    },
+}
+
+/// A way to print to terminal why the record could not be used
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::MalformedTag { line } => {
+                write!(f, "line {line} is not a `[Tag \"value\"]` header")
+            }
+            RecordError::MissingTag(tag) => write!(f, "missing the `{tag}` tag"),
+            RecordError::InvalidTag { tag, value } => {
+                write!(f, "invalid value `{value}` for the `{tag}` tag")
+            }
+            RecordError::InvalidMove { number, text } => {
+                write!(f, "move {number} `{text}` is not a square")
+            }
+            RecordError::UnsupportedVariant(variant) => {
+                write!(f, "the `{variant}` variant is not supported")
+            }
+            RecordError::InvalidSetup(error) => write!(f, "invalid setup: {error}"),
+            RecordError::IllegalMove { number, error } => {
+                write!(f, "move {number} is illegal: {error}")
+            }
+            RecordError::ResultMismatch { recorded, replayed } => write!(
+                f,
+                "the record says `{recorded}` but replaying it gives `{replayed}`"
+            ),
+        }
+    }
+}
+
+impl Error for RecordError {}
+
+/// Everything needed to store, share and replay a complete game.
+///
+/// Records are written as a PGN-like text: a header of `[Tag "value"]` lines,
+/// a blank line and then the numbered move list. Squares are written in algebraic
+/// notation, see [`Coordinates`].
+///
+/// ```text
+/// [Size "5x5"]
+/// [Variant "Standard"]
+/// [Setup "a1 e5"]
+/// [Red "Alice"]
+/// [Blue "Bob"]
+/// [Engine "movetime 250ms"]
+/// [Started "2026-10-17T12:00:00Z"]
+/// [Finished "2026-10-17T12:03:10Z"]
+/// [Result "Ongoing"]
+///
+/// 1. a1 2. e5 3. b1
+/// ```
+///
+/// `Size`, `Setup` and `Result` are required, `Variant` defaults to `Standard`,
+/// and every other tag is optional. Unknown tags are ignored when reading.
+///
+/// Inside a value, a quote is written `\"`, a backslash `\\`, and line breaks
+/// `\n` and `\r`, so any name or setting can be stored on its header line.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "GameRecordFields", into = "GameRecordFields")
+)]
+pub struct GameRecord {
+    dimensions: Dimensions,
+    variant: String,
+    placements: Vec<Coordinates>,
+    moves: Vec<Coordinates>,
+    result: GameResult,
+    player_names: Vec<Option<String>>,
+    engine_settings: Option<String>,
+    started_at: Option<String>,
+    finished_at: Option<String>,
+}
+
+impl GameRecord {
+    /// Creates a record of the moves played so far in `game`, without any metadata.
+    pub fn new(game: &Game) -> Self {
+        Self {
+            dimensions: game.dimensions(),
+            variant: String::from(STANDARD_VARIANT),
+            placements: game.placements().to_vec(),
+            moves: game.moves().to_vec(),
+            result: game.result(),
+            player_names: vec![None; game.placements().len()],
+            engine_settings: None,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    /// Returns the size of the board.
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    /// Returns the name of the rule variant the game was played with.
+    pub fn variant(&self) -> &str {
+        &self.variant
+    }
+
+    /// Returns the starting square of every player, in turn order.
+    pub fn placements(&self) -> &[Coordinates] {
+        &self.placements
+    }
+
+    /// Returns the moves in the order they were played.
+    pub fn moves(&self) -> &[Coordinates] {
+        &self.moves
+    }
+
+    /// Returns the recorded result.
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    /// Returns the name of `player`, if one was recorded.
+    pub fn player_name(&self, player: Player) -> Option<&str> {
+        self.player_names.get(player.index())?.as_deref()
+    }
+
+    /// Records the name of `player`.
+    ///
+    /// # Panics
+    /// Panics if `player` does not take part in the game.
+    pub fn set_player_name(&mut self, player: Player, name: &str) {
+        self.player_names[player.index()] = Some(name.to_string());
+    }
+
+    /// Returns the engine settings used during the game, if they were recorded.
+    pub fn engine_settings(&self) -> Option<&str> {
+        self.engine_settings.as_deref()
+    }
+
+    /// Records the engine settings used during the game, in any format.
+    pub fn set_engine_settings(&mut self, settings: &str) {
+        self.engine_settings = Some(settings.to_string());
+    }
+
+    /// Returns when the game started, if it was recorded.
+    pub fn started_at(&self) -> Option<&str> {
+        self.started_at.as_deref()
+    }
+
+    /// Records when the game started. RFC 3339 timestamps are recommended.
+    pub fn set_started_at(&mut self, timestamp: &str) {
+        self.started_at = Some(timestamp.to_string());
+    }
+
+    /// Returns when the game finished, if it was recorded.
+    pub fn finished_at(&self) -> Option<&str> {
+        self.finished_at.as_deref()
+    }
+
+    /// Records when the game finished. RFC 3339 timestamps are recommended.
+    pub fn set_finished_at(&mut self, timestamp: &str) {
+        self.finished_at = Some(timestamp.to_string());
+    }
+
+    /// Replays the record through [`Game::play`], checking every move is legal and
+    /// that the game ends with the recorded result.
+    ///
+    /// # Errors
+    /// Returns a `RecordError` for an unsupported variant, an invalid setup, the first
+    /// illegal move along with its move number, or a result that does not match.
+    pub fn replay(&self) -> Result<Game, RecordError> {
+        if self.variant != STANDARD_VARIANT {
+            return Err(RecordError::UnsupportedVariant(self.variant.clone()));
+        }
+
+        let mut game =
+            Game::try_new(self.dimensions, &self.placements).map_err(RecordError::InvalidSetup)?;
+
+        for (index, placement) in self.moves.iter().enumerate() {
+            game.play(*placement)
+                .map_err(|error| RecordError::IllegalMove {
+                    number: index + 1,
+                    error,
+                })?;
+        }
+
+        if self.result == GameResult::Drawn && game.result() == GameResult::Ongoing {
+            game.declare_draw()
+                .expect("an ongoing game can always be drawn");
+        }
+        if game.result() != self.result {
+            return Err(RecordError::ResultMismatch {
+                recorded: self.result,
+                replayed: game.result(),
+            });
+        }
+
+        Ok(game)
+    }
+}
+
+/// Writes the record in the plain-text format described in [`GameRecord`].
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let setup: Vec<String> = self.placements.iter().map(ToString::to_string).collect();
+
+        writeln!(f, "[Size \"{}\"]", self.dimensions)?;
+        writeln!(f, "[Variant \"{}\"]", escape(&self.variant))?;
+        writeln!(f, "[Setup \"{}\"]", setup.join(" "))?;
+        for (player, name) in Player::ALL.iter().zip(&self.player_names) {
+            if let Some(name) = name {
+                writeln!(f, "[{player} \"{}\"]", escape(name))?;
+            }
+        }
+        if let Some(settings) = &self.engine_settings {
+            writeln!(f, "[Engine \"{}\"]", escape(settings))?;
+        }
+        if let Some(timestamp) = &self.started_at {
+            writeln!(f, "[Started \"{}\"]", escape(timestamp))?;
+        }
+        if let Some(timestamp) = &self.finished_at {
+            writeln!(f, "[Finished \"{}\"]", escape(timestamp))?;
+        }
+        writeln!(f, "[Result \"{}\"]", self.result)?;
+        writeln!(f)?;
+
+        let moves: Vec<String> = self
+            .moves
+            .iter()
+            .enumerate()
+            .map(|(index, placement)| format!("{}. {placement}", index + 1))
+            .collect();
+        writeln!(f, "{}", moves.join(" "))
+    }
+}
+
+/// Reads a record written in the plain-text format described in [`GameRecord`].
+///
+/// Reading only checks the record is well formed, use [`GameRecord::replay`]
+/// to check the moves are legal.
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut tags = Vec::new();
+        let mut move_tokens = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                tags.push(parse_tag(line).ok_or(RecordError::MalformedTag { line: index + 1 })?);
+            } else {
+                move_tokens.extend(line.split_whitespace());
+            }
+        }
+
+        let tag = |name: &'static str| {
+            tags.iter()
+                .find(|(tag, _)| *tag == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let invalid_tag = |name: &str, value: &str| RecordError::InvalidTag {
+            tag: name.to_string(),
+            value: value.to_string(),
+        };
+
+        let size = tag("Size").ok_or(RecordError::MissingTag("Size"))?;
+        let dimensions = size
+            .parse::<Dimensions>()
+            .map_err(|_| invalid_tag("Size", size))?;
+
+        let setup = tag("Setup").ok_or(RecordError::MissingTag("Setup"))?;
+        let placements = setup
+            .split_whitespace()
+            .map(|square| Coordinates::parse(square, dimensions))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid_tag("Setup", setup))?;
+
+        let result_text = tag("Result").ok_or(RecordError::MissingTag("Result"))?;
+        let result = parse_result(result_text).ok_or_else(|| invalid_tag("Result", result_text))?;
+
+        let player_names = Player::ALL[..placements.len().min(Player::MAX_PLAYERS)]
+            .iter()
+            .map(|player| {
+                tags.iter()
+                    .find(|(tag, _)| *tag == player.to_string())
+                    .map(|(_, name)| name.clone())
+            })
+            .collect();
+
+        // Move numbers such as `12.` are only there for readers, so they are skipped
+        let mut moves = Vec::new();
+        for token in move_tokens {
+            if token.ends_with('.') && token[..token.len() - 1].parse::<usize>().is_ok() {
+                continue;
+            }
+            let placement =
+                Coordinates::parse(token, dimensions).map_err(|_| RecordError::InvalidMove {
+                    number: moves.len() + 1,
+                    text: token.to_string(),
+                })?;
+            moves.push(placement);
+        }
+
+        Ok(Self {
+            dimensions,
+            variant: tag("Variant").unwrap_or(STANDARD_VARIANT).to_string(),
+            placements,
+            moves,
+            result,
+            player_names,
+            engine_settings: tag("Engine").map(str::to_string),
+            started_at: tag("Started").map(str::to_string),
+            finished_at: tag("Finished").map(str::to_string),
+        })
+    }
+}
+
+// Splits a `[Tag "value"]` line into its tag and unescaped value.
+fn parse_tag(line: &str) -> Option<(&str, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (tag, value) = inner.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((tag, unescape(value)?))
+}
+
+// Escapes the characters a tag value cannot hold as they are.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+// Reverses `escape`, or returns `None` for an unknown escape or an unescaped quote.
+fn unescape(value: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut characters = value.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => unescaped.push(match characters.next()? {
+                '"' => '"',
+                '\\' => '\\',
+                'n' => '\n',
+                'r' => '\r',
+                _ => return None,
+            }),
+            '"' => return None,
+            _ => unescaped.push(character),
+        }
+    }
+    Some(unescaped)
+}
+
+fn parse_result(text: &str) -> Option<GameResult> {
+    match text {
+        "Ongoing" => Some(GameResult::Ongoing),
+        "Draw" => Some(GameResult::Drawn),
+        _ => {
+            let name = text.strip_suffix(" wins")?;
+            Player::ALL
+                .into_iter()
+                .find(|player| player.to_string() == name)
+                .map(GameResult::Won)
+        }
+    }
+}
+
+// The serialized form of a `GameRecord`, checked like a parsed record before it
+// becomes one.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct GameRecordFields {
+    dimensions: Dimensions,
+    variant: String,
+    placements: Vec<Coordinates>,
+    moves: Vec<Coordinates>,
+    result: GameResult,
+    player_names: Vec<Option<String>>,
+    engine_settings: Option<String>,
+    started_at: Option<String>,
+    finished_at: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GameRecordFields> for GameRecord {
+    type Error = String;
+
+    fn try_from(fields: GameRecordFields) -> Result<Self, Self::Error> {
+        if let Some(square) = fields
+            .placements
+            .iter()
+            .chain(&fields.moves)
+            .find(|square| !fields.dimensions.contains(**square))
+        {
+            return Err(format!(
+                "{square} is outside the {} board",
+                fields.dimensions
+            ));
+        }
+        // Names are kept for the players taking part, like when reading a record
+        let players = fields.placements.len().min(Player::MAX_PLAYERS);
+        if fields.player_names.len() != players {
+            return Err(format!(
+                "{} player names for {players} players",
+                fields.player_names.len()
+            ));
+        }
+
+        Ok(Self {
+            dimensions: fields.dimensions,
+            variant: fields.variant,
+            placements: fields.placements,
+            moves: fields.moves,
+            result: fields.result,
+            player_names: fields.player_names,
+            engine_settings: fields.engine_settings,
+            started_at: fields.started_at,
+            finished_at: fields.finished_at,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<GameRecord> for GameRecordFields {
+    fn from(record: GameRecord) -> Self {
+        GameRecordFields {
+            dimensions: record.dimensions,
+            variant: record.variant,
+            placements: record.placements,
+            moves: record.moves,
+            result: record.result,
+            player_names: record.player_names,
+            engine_settings: record.engine_settings,
+            started_at: record.started_at,
+            finished_at: record.finished_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Helper function to create a game with a few moves for testing
+    fn setup_test_game() -> Game {
+        let dimensions = Dimensions::default();
+        let mut game = Game::new(
+            dimensions,
+            &[
+                Coordinates::new(0, 0, dimensions),
+                Coordinates::new(4, 4, dimensions),
+            ],
+        );
+        for _ in 0..3 {
+            game.play(game.board().get_valid_moves()[0]).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn write_record() {
+        let mut record = GameRecord::new(&setup_test_game());
+        record.set_player_name(Player::Red, "Alice");
+        record.set_engine_settings("movetime 250ms");
+        record.set_started_at("2026-10-17T12:00:00Z");
+
+        assert_eq!(
+            record.to_string(),
+            "[Size \"5x5\"]\n\
+             [Variant \"Standard\"]\n\
+             [Setup \"a1 e5\"]\n\
+             [Red \"Alice\"]\n\
+             [Engine \"movetime 250ms\"]\n\
+             [Started \"2026-10-17T12:00:00Z\"]\n\
+             [Result \"Ongoing\"]\n\
+             \n\
+             1. a1 2. e5 3. b1\n"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let game = setup_test_game();
+        let mut record = GameRecord::new(&game);
+        record.set_player_name(Player::Blue, "Bob");
+        record.set_finished_at("2026-10-17T12:03:10Z");
+
+        let parsed: GameRecord = record.to_string().parse().unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.player_name(Player::Blue), Some("Bob"));
+        assert_eq!(parsed.player_name(Player::Red), None);
+        assert_eq!(parsed.replay(), Ok(game));
+    }
+
+    #[test]
+    fn escaped_tags() {
+        let mut record = GameRecord::new(&setup_test_game());
+        record.set_player_name(Player::Red, "Alice \"The Red\" Smith");
+        record.set_player_name(Player::Blue, "C:\\Users\\bob");
+        record.set_engine_settings("depth 3\nthreads 2\r");
+
+        let text = record.to_string();
+        assert!(text.contains("[Red \"Alice \\\"The Red\\\" Smith\"]\n"));
+        assert!(text.contains("[Blue \"C:\\\\Users\\\\bob\"]\n"));
+        assert!(text.contains("[Engine \"depth 3\\nthreads 2\\r\"]\n"));
+
+        let parsed: GameRecord = text.parse().unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(
+            parsed.player_name(Player::Red),
+            Some("Alice \"The Red\" Smith")
+        );
+        assert_eq!(parsed.engine_settings(), Some("depth 3\nthreads 2\r"));
+    }
+
+    #[test]
+    fn replay_finished_game() {
+        let dimensions = Dimensions::new(2, 2);
+        let mut game = Game::new(
+            dimensions,
+            &[
+                Coordinates::new(0, 0, dimensions),
+                Coordinates::new(0, 1, dimensions),
+            ],
+        );
+        game.play(Coordinates::new(0, 0, dimensions)).unwrap();
+
+        let record: GameRecord = GameRecord::new(&game).to_string().parse().unwrap();
+        assert_eq!(record.result(), GameResult::Won(Player::Red));
+        assert_eq!(
+            record.replay().unwrap().result(),
+            GameResult::Won(Player::Red)
+        );
+    }
+
+    #[test]
+    fn replay_drawn_game() {
+        let mut game = setup_test_game();
+        game.declare_draw().unwrap();
+
+        let record = GameRecord::new(&game);
+        assert_eq!(record.replay(), Ok(game));
+    }
+
+    #[test]
+    fn replay_illegal_move() {
+        let text = "[Size \"5x5\"]\n[Setup \"a1 e5\"]\n[Result \"Ongoing\"]\n\n1. a1 2. b1\n";
+        let record: GameRecord = text.parse().unwrap();
+
+        assert_eq!(
+            record.replay(),
+            Err(RecordError::IllegalMove {
+                number: 2,
+                error: MoveError::NotYourSquare {
+                    coordinates: Coordinates::new(0, 1, Dimensions::default()),
+                    owner: Player::Red
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn replay_result_mismatch() {
+        let text = "[Size \"5x5\"]\n[Setup \"a1 e5\"]\n[Result \"Blue wins\"]\n\n1. a1\n";
+        let record: GameRecord = text.parse().unwrap();
+
+        assert_eq!(
+            record.replay(),
+            Err(RecordError::ResultMismatch {
+                recorded: GameResult::Won(Player::Blue),
+                replayed: GameResult::Ongoing
+            })
+        );
+    }
+
+    #[test]
+    fn replay_unsupported_variant() {
+        let text = "[Size \"5x5\"]\n[Variant \"Blitz\"]\n[Setup \"a1 e5\"]\n[Result \"Ongoing\"]\n";
+        let record: GameRecord = text.parse().unwrap();
+
+        assert_eq!(
+            record.replay(),
+            Err(RecordError::UnsupportedVariant(String::from("Blitz")))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |text: &str| text.parse::<GameRecord>().unwrap_err();
+
+        assert_eq!(
+            error("[Size \"5x5\"]\n[Setup a1]\n"),
+            RecordError::MalformedTag { line: 2 }
+        );
+        assert_eq!(
+            error("[Size \"5x5\"]\n[Red \"a\"b\"]\n"),
+            RecordError::MalformedTag { line: 2 }
+        );
+        assert_eq!(
+            error("[Size \"5x5\"]\n[Red \"a\\tb\"]\n"),
+            RecordError::MalformedTag { line: 2 }
+        );
+        assert_eq!(
+            error("[Setup \"a1 e5\"]\n[Result \"Ongoing\"]\n"),
+            RecordError::MissingTag("Size")
+        );
+        assert_eq!(
+            error("[Size \"5x5\"]\n[Setup \"a1 f6\"]\n[Result \"Ongoing\"]\n"),
+            RecordError::InvalidTag {
+                tag: String::from("Setup"),
+                value: String::from("a1 f6")
+            }
+        );
+        assert_eq!(
+            error("[Size \"5x5\"]\n[Setup \"a1 e5\"]\n[Result \"Purple wins\"]\n"),
+            RecordError::InvalidTag {
+                tag: String::from("Result"),
+                value: String::from("Purple wins")
+            }
+        );
+        assert_eq!(
+            error("[Size \"5x5\"]\n[Setup \"a1 e5\"]\n[Result \"Ongoing\"]\n\n1. a1 2. j10\n"),
+            RecordError::InvalidMove {
+                number: 2,
+                text: String::from("j10")
+            }
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut record = GameRecord::new(&setup_test_game());
+        record.set_player_name(Player::Red, "Alice");
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(serde_json::from_str::<GameRecord>(&json).unwrap(), record);
+
+        let record = |placements: &str, player_names: &str| {
+            format!(
+                r#"{{"dimensions":{{"rows":5,"columns":5}},"variant":"Standard","placements":{placements},"moves":["a1"],"result":"Ongoing","player_names":{player_names},"engine_settings":null,"started_at":null,"finished_at":null}}"#
+            )
+        };
+        assert!(
+            serde_json::from_str::<GameRecord>(&record(r#"["a1","e5"]"#, "[null,null]")).is_ok()
+        );
+        assert!(
+            serde_json::from_str::<GameRecord>(&record(r#"["a1","f6"]"#, "[null,null]")).is_err()
+        );
+        assert!(serde_json::from_str::<GameRecord>(&record(r#"["a1","e5"]"#, "[null]")).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+//! Defines the `Score` type, the engine's evaluation of a position.
+use std::{fmt, ops::Neg};
+
+/// How good a position is for one side, either an evaluation or a win or loss a
+/// known number of plies away.
+///
+/// Scores are ordered from worst to best: losses come first, the sooner the worse,
+/// then evaluations, then wins, the sooner the better. This is what makes the engine
+/// prefer a quick win over a slow one, and delay a loss as long as it can.
+///
+/// # Example
+///
+/// ```
+/// use color_wars::prelude::*;
+///
+/// assert!(Score::win_in(3) > Score::win_in(15));
+/// assert!(Score::win_in(15) > Score::evaluation(100));
+/// assert!(Score::loss_in(3) < Score::loss_in(15));
+/// assert_eq!(-Score::win_in(5), Score::loss_in(5));
+/// assert_eq!(Score::win_in(5).to_string(), "W5");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "ScoreFields", into = "ScoreFields")
+)]
+pub struct Score(i32);
+
+impl Score {
+    /// The largest number of plies a win or a loss can be away.
+    pub const MAX_PLIES: usize = 1 << 16;
+
+    // The raw value of a win on the spot. Wins further away are counted down from it.
+    const WIN: i32 = 1 << 30;
+
+    // The largest raw value of an evaluation, below every win.
+    const MAX_EVALUATION: i32 = Self::WIN - Self::MAX_PLIES as i32 - 1;
+
+    /// Creates the score of an undecided position.
+    ///
+    /// Values too large to be told apart from wins and losses are clamped.
+    pub fn evaluation(value: i32) -> Self {
+        Self(value.clamp(-Self::MAX_EVALUATION, Self::MAX_EVALUATION))
+    }
+
+    /// Creates the score of a win `plies` plies away.
+    ///
+    /// # Panics
+    /// Panics if `plies` is greater than `MAX_PLIES`.
+    pub const fn win_in(plies: usize) -> Self {
+        assert!(plies <= Self::MAX_PLIES);
+        Self(Self::WIN - plies as i32)
+    }
+
+    /// Creates the score of a loss `plies` plies away.
+    ///
+    /// # Panics
+    /// Panics if `plies` is greater than `MAX_PLIES`.
+    pub fn loss_in(plies: usize) -> Self {
+        -Self::win_in(plies)
+    }
+
+    /// Returns whether the score is a win.
+    pub fn is_win(&self) -> bool {
+        self.0 > Self::MAX_EVALUATION
+    }
+
+    /// Returns whether the score is a loss.
+    pub fn is_loss(&self) -> bool {
+        self.0 < -Self::MAX_EVALUATION
+    }
+
+    /// Returns the number of plies until the win or loss, or `None` for an evaluation.
+    pub fn plies(&self) -> Option<usize> {
+        (self.is_win() || self.is_loss()).then(|| (Self::WIN - self.0.abs()) as usize)
+    }
+
+    /// Returns the evaluation, or `None` for a win or a loss.
+    pub fn value(&self) -> Option<i32> {
+        self.plies().is_none().then_some(self.0)
+    }
+
+    /// Returns the score of the same outcome seen from `plies` plies earlier, so a
+    /// win or a loss is that much further away.
+    ///
+    /// Wins and losses never get further than `MAX_PLIES`.
+    pub(crate) fn plies_earlier(self, plies: usize) -> Self {
+        match self.plies() {
+            None => self,
+            Some(distance) if self.is_win() => {
+                Self::win_in((distance + plies).min(Self::MAX_PLIES))
+            }
+            Some(distance) => Self::loss_in((distance + plies).min(Self::MAX_PLIES)),
+        }
+    }
+
+    /// Returns the score of the same outcome seen from `plies` plies later, so a win
+    /// or a loss is that much closer.
+    pub(crate) fn plies_later(self, plies: usize) -> Self {
+        match self.plies() {
+            None => self,
+            Some(distance) if self.is_win() => Self::win_in(distance.saturating_sub(plies)),
+            Some(distance) => Self::loss_in(distance.saturating_sub(plies)),
+        }
+    }
+
+    /// Creates a score from the value returned by `raw`.
+    pub(crate) fn from_raw(raw: i32) -> Self {
+        Self(raw.clamp(-Self::WIN, Self::WIN))
+    }
+
+    /// Returns the score as a plain integer, ordered like scores, which the search
+    /// uses for its windows.
+    pub(crate) const fn raw(self) -> i32 {
+        self.0
+    }
+}
+
+/// The same score seen from the other side.
+impl Neg for Score {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self(-self.0)
+    }
+}
+
+/// Formats wins as `W` and losses as `L` followed by the number of plies, e.g. `W5`,
+/// and evaluations as a number.
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.plies() {
+            Some(plies) if self.is_win() => write!(f, "W{plies}"),
+            Some(plies) => write!(f, "L{plies}"),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+// The serialized form of a `Score`, its raw value, checked before it becomes one.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ScoreFields(i32);
+
+#[cfg(feature = "serde")]
+impl TryFrom<ScoreFields> for Score {
+    type Error = String;
+
+    fn try_from(fields: ScoreFields) -> Result<Self, Self::Error> {
+        if !(-Self::WIN..=Self::WIN).contains(&fields.0) {
+            return Err(format!(
+                "{} is not a score, expected {} to {}",
+                fields.0,
+                -Self::WIN,
+                Self::WIN
+            ));
+        }
+        Ok(Self::from_raw(fields.0))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Score> for ScoreFields {
+    fn from(score: Score) -> Self {
+        ScoreFields(score.raw())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordering() {
+        let scores = [
+            Score::loss_in(0),
+            Score::loss_in(3),
+            Score::loss_in(15),
+            Score::evaluation(i32::MIN),
+            Score::evaluation(-20),
+            Score::evaluation(0),
+            Score::evaluation(20),
+            Score::evaluation(i32::MAX),
+            Score::win_in(15),
+            Score::win_in(3),
+            Score::win_in(0),
+        ];
+        assert!(scores.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn wins_and_losses() {
+        assert!(Score::win_in(5).is_win());
+        assert!(!Score::win_in(5).is_loss());
+        assert!(Score::loss_in(5).is_loss());
+        assert_eq!(Score::win_in(5).plies(), Some(5));
+        assert_eq!(
+            Score::loss_in(Score::MAX_PLIES).plies(),
+            Some(Score::MAX_PLIES)
+        );
+        assert_eq!(Score::win_in(5).value(), None);
+
+        let evaluation = Score::evaluation(i32::MAX);
+        assert!(!evaluation.is_win());
+        assert_eq!(evaluation.plies(), None);
+        assert_eq!(Score::evaluation(-12).value(), Some(-12));
+    }
+
+    #[test]
+    fn negation() {
+        assert_eq!(-Score::win_in(5), Score::loss_in(5));
+        assert_eq!(-Score::loss_in(0), Score::win_in(0));
+        assert_eq!(-Score::evaluation(12), Score::evaluation(-12));
+        assert_eq!(-Score::evaluation(i32::MIN), Score::evaluation(i32::MAX));
+    }
+
+    #[test]
+    fn distance() {
+        assert_eq!(Score::win_in(2).plies_earlier(3), Score::win_in(5));
+        assert_eq!(Score::loss_in(5).plies_later(3), Score::loss_in(2));
+        assert_eq!(Score::win_in(2).plies_later(3), Score::win_in(0));
+        assert_eq!(Score::evaluation(7).plies_earlier(3), Score::evaluation(7));
+        assert_eq!(Score::from_raw(Score::win_in(4).raw()), Score::win_in(4));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Score::win_in(5).to_string(), "W5");
+        assert_eq!(Score::loss_in(12).to_string(), "L12");
+        assert_eq!(Score::evaluation(-7).to_string(), "-7");
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_far() {
+        Score::win_in(Score::MAX_PLIES + 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        for score in [Score::win_in(5), Score::loss_in(0), Score::evaluation(-12)] {
+            let json = serde_json::to_string(&score).unwrap();
+            assert_eq!(serde_json::from_str::<Score>(&json).unwrap(), score);
+        }
+        assert_eq!(serde_json::to_string(&Score::evaluation(7)).unwrap(), "7");
+        assert!(serde_json::from_str::<Score>(&(Score::WIN + 1).to_string()).is_err());
+        assert!(serde_json::from_str::<Score>(&i32::MIN.to_string()).is_err());
+    }
+}
@@ -7,6 +7,11 @@ use crate::player::Player;
 /// - An **empty** square has an owner of `None` and a value of `0`.
 /// - An **occupied** square has an owner of `Some(Player)` and a value from `1` to `3`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(from = "Option<OccupiedFields>", into = "Option<OccupiedFields>")
+)]
 pub enum Square {
     Empty,
     Occupied { owner: Player, value: u8 },
@@ -65,6 +70,34 @@ impl Square {
     }
 }
 
+// The serialized form of an occupied square, an empty square is serialized as `null`.
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+struct OccupiedFields {
+    owner: Player,
+    value: u8,
+}
+
+#[cfg(feature = "serde")]
+impl From<Option<OccupiedFields>> for Square {
+    fn from(fields: Option<OccupiedFields>) -> Self {
+        match fields {
+            Some(OccupiedFields { owner, value }) => Self::occupied(owner, value),
+            None => Self::empty(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Square> for Option<OccupiedFields> {
+    fn from(square: Square) -> Self {
+        square.owner().map(|owner| OccupiedFields {
+            owner,
+            value: square.value(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -112,4 +145,17 @@ mod tests {
         // After reset, it should be identical to a default square
         assert_eq!(s, Square::empty());
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let occupied = Square::occupied(Player::Red, 3);
+        let json = serde_json::to_string(&[occupied, Square::empty()]).unwrap();
+
+        assert_eq!(json, r#"[{"owner":"Red","value":3},null]"#);
+        assert_eq!(
+            serde_json::from_str::<[Square; 2]>(&json).unwrap(),
+            [occupied, Square::empty()]
+        );
+    }
 }
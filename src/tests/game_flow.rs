@@ -3,7 +3,14 @@ use color_wars::prelude::*;
 #[test]
 fn test_a_few_turns() {
     // 1. Setup the board
-    let mut board = Board::setup(Coordinates::new(0, 0), Coordinates::new(4, 4));
+    let dimensions = Dimensions::default();
+    let mut board = Board::setup(
+        dimensions,
+        &[
+            Coordinates::new(0, 0, dimensions),
+            Coordinates::new(4, 4, dimensions),
+        ],
+    );
     assert!(!board.is_game_over());
     assert_eq!(board.turn(), Player::Red);
 
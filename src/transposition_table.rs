@@ -0,0 +1,297 @@
+//! Defines the `TranspositionTable`, the engine's fixed-size cache of searched positions.
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::coordinates::Coordinates;
+
+/// How a stored score relates to the true score of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Bound {
+    /// The score is the true score.
+    Exact,
+    /// The search failed high, the true score is at least the stored score.
+    Lower,
+    /// The search failed low, the true score is at most the stored score.
+    Upper,
+}
+
+/// The result of searching a position, as stored in the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Entry {
+    pub(crate) key: u64,
+    pub(crate) depth: usize,
+    pub(crate) score: i32,
+    pub(crate) bound: Bound,
+    pub(crate) best_move: Option<Coordinates>,
+    generation: u8,
+}
+
+impl Bound {
+    // The code of the bound in a packed entry, `0` marks an empty slot.
+    fn code(self) -> u64 {
+        match self {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        }
+    }
+
+    fn from_code(code: u64) -> Option<Self> {
+        match code {
+            1 => Some(Bound::Exact),
+            2 => Some(Bound::Lower),
+            3 => Some(Bound::Upper),
+            _ => None,
+        }
+    }
+}
+
+impl Entry {
+    // Packs the entry into a data word and a move word.
+    fn pack(&self) -> [u64; 2] {
+        let data = self.score as u32 as u64
+            | (self.depth.min(u16::MAX as usize) as u64) << 32
+            | self.bound.code() << 48
+            | (self.generation as u64) << 50;
+        let best_move = self.best_move.map_or(NO_MOVE, |best_move| {
+            (best_move.row() as u64) << 32 | best_move.column() as u64
+        });
+        [data, best_move]
+    }
+
+    // Unpacks the entry of a position with the given `key`, `None` for an empty slot.
+    fn unpack(key: u64, [data, best_move]: [u64; 2]) -> Option<Self> {
+        Some(Self {
+            key,
+            depth: (data >> 32 & 0xFFFF) as usize,
+            score: data as u32 as i32,
+            bound: Bound::from_code(data >> 48 & 0b11)?,
+            best_move: (best_move != NO_MOVE).then(|| {
+                Coordinates::from_indexes((best_move >> 32) as usize, best_move as u32 as usize)
+            }),
+            generation: (data >> 50) as u8,
+        })
+    }
+}
+
+// The move word of an entry without a best move.
+const NO_MOVE: u64 = u64::MAX;
+
+/// A fixed-size cache of search results, keyed by `Board::hash_key`.
+///
+/// Every position maps to a single slot. When two positions compete for a slot,
+/// the deeper search is kept, unless the stored one is left over from an earlier
+/// search, so the table stays useful when it is reused for every move of a game.
+///
+/// The table is lock-free, so the threads of a search all share one table. An
+/// entry is stored as a data word, a move word and a check word, the XOR of the
+/// position's key with both. An entry torn by two threads writing its slot at once
+/// does not check out and reads as missing.
+#[derive(Debug)]
+pub struct TranspositionTable {
+    entries: Vec<[AtomicU64; 3]>,
+    generation: u8,
+}
+
+impl TranspositionTable {
+    /// The number of entries of a table created with `default`.
+    pub const DEFAULT_CAPACITY: usize = 1 << 16;
+
+    /// Creates an empty table that holds at most `capacity` positions.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is `0`.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            entries: (0..capacity).map(|_| Default::default()).collect(),
+            generation: 0,
+        }
+    }
+
+    /// Returns the number of positions the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the number of positions stored in the table.
+    pub fn len(&self) -> usize {
+        (0..self.capacity())
+            .filter(|slot| self.read(*slot).is_some())
+            .count()
+    }
+
+    /// Returns whether no position is stored in the table.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every stored position.
+    pub fn clear(&mut self) {
+        for words in &mut self.entries {
+            *words = Default::default();
+        }
+        self.generation = 0;
+    }
+
+    /// Marks the entries stored so far as belonging to an earlier search.
+    pub(crate) fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Returns the stored result for the position with the given `key`, if any.
+    pub(crate) fn probe(&self, key: u64) -> Option<Entry> {
+        self.read(self.slot(key)).filter(|entry| entry.key == key)
+    }
+
+    /// Stores the result of searching the position with the given `key`.
+    pub(crate) fn store(
+        &self,
+        key: u64,
+        depth: usize,
+        score: i32,
+        bound: Bound,
+        best_move: Option<Coordinates>,
+    ) {
+        let slot = self.slot(key);
+        let replace = match self.read(slot) {
+            None => true,
+            Some(entry) => {
+                entry.key == key || entry.generation != self.generation || depth >= entry.depth
+            }
+        };
+        if replace {
+            let entry = Entry {
+                key,
+                depth,
+                score,
+                bound,
+                best_move,
+                generation: self.generation,
+            };
+            let [data, best_move] = entry.pack();
+            let [data_word, move_word, check_word] = &self.entries[slot];
+            data_word.store(data, Ordering::Relaxed);
+            move_word.store(best_move, Ordering::Relaxed);
+            check_word.store(key ^ data ^ best_move, Ordering::Relaxed);
+        }
+    }
+
+    // The slot a key is stored in.
+    fn slot(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    // Reads the entry in `slot`, `None` if it is empty.
+    fn read(&self, slot: usize) -> Option<Entry> {
+        let [data, best_move, check] = self.entries[slot]
+            .each_ref()
+            .map(|word| word.load(Ordering::Relaxed));
+        Entry::unpack(check ^ data ^ best_move, [data, best_move])
+    }
+}
+
+impl Clone for TranspositionTable {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self
+                .entries
+                .iter()
+                .map(|words| {
+                    words
+                        .each_ref()
+                        .map(|word| AtomicU64::new(word.load(Ordering::Relaxed)))
+                })
+                .collect(),
+            generation: self.generation,
+        }
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimensions::Dimensions;
+
+    fn test_move() -> Coordinates {
+        Coordinates::new(0, 0, Dimensions::default())
+    }
+
+    #[test]
+    fn store_and_probe() {
+        let mut table = TranspositionTable::new(8);
+        assert!(table.is_empty());
+        assert_eq!(table.probe(3), None);
+
+        table.store(3, 2, 10, Bound::Exact, Some(test_move()));
+        let entry = table.probe(3).unwrap();
+        assert_eq!(entry.depth, 2);
+        assert_eq!(entry.score, 10);
+        assert_eq!(entry.bound, Bound::Exact);
+        assert_eq!(entry.best_move, Some(test_move()));
+        assert_eq!(table.len(), 1);
+
+        // Another key in the same slot is not a match
+        assert_eq!(table.probe(11), None);
+
+        table.clear();
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn replacement_prefers_depth_then_age() {
+        let mut table = TranspositionTable::new(8);
+        table.store(3, 4, 10, Bound::Exact, None);
+
+        // A shallower search of another position does not replace a deeper one
+        table.store(11, 2, 20, Bound::Lower, None);
+        assert_eq!(table.probe(3).map(|entry| entry.score), Some(10));
+        assert_eq!(table.probe(11), None);
+
+        // The same position is always replaced
+        table.store(3, 1, 30, Bound::Upper, None);
+        assert_eq!(table.probe(3).map(|entry| entry.score), Some(30));
+
+        // Entries from an earlier search are replaced
+        table.store(3, 4, 10, Bound::Exact, None);
+        table.new_search();
+        table.store(11, 2, 20, Bound::Lower, None);
+        assert_eq!(table.probe(11).map(|entry| entry.score), Some(20));
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn torn_entries_read_as_missing() {
+        let table = TranspositionTable::new(8);
+        table.store(3, 2, 10, Bound::Exact, Some(test_move()));
+        let cloned = table.clone();
+
+        // Another thread overwrote the data word but not the rest of the entry
+        table.entries[3][0].store(0x1234, Ordering::Relaxed);
+        assert_eq!(table.probe(3), None);
+        assert_eq!(cloned.probe(3).map(|entry| entry.score), Some(10));
+    }
+
+    #[test]
+    fn scores_and_moves_round_trip() {
+        let table = TranspositionTable::new(8);
+        let best_move = Coordinates::new(40, 300, Dimensions::new(41, 301));
+        table.store(5, 70_000, -12, Bound::Upper, Some(best_move));
+        let entry = table.probe(5).unwrap();
+        assert_eq!(entry.score, -12);
+        assert_eq!(entry.depth, u16::MAX as usize);
+        assert_eq!(entry.bound, Bound::Upper);
+        assert_eq!(entry.best_move, Some(best_move));
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_capacity() {
+        TranspositionTable::new(0);
+    }
+}
@@ -0,0 +1,393 @@
+//! Tunes the `EvalParams` of the `StandardEvaluator` from self-play games with the
+//! Texel method: the engine plays itself, and the weights are adjusted until the
+//! evaluations of the positions it reached best predict how its games ended.
+use crate::{
+    board::Board,
+    dimensions::Dimensions,
+    engine::{SearchLimits, StopHandle, is_explosive, search_with_evaluator},
+    eval_params::EvalParams,
+    evaluator::{Evaluator, StandardEvaluator, evaluate},
+    game::{Game, GameResult},
+    player::Player,
+    random::Random,
+    transposition_table::TranspositionTable,
+    zobrist,
+};
+
+/// The number of random moves that open every self-play game, so the games differ.
+const OPENING_MOVES: usize = 4;
+
+/// How many moves per square of the board a self-play game may last before it is
+/// declared a draw.
+const GAME_MOVES_PER_SQUARE: usize = 4;
+
+/// The largest number of times `tune` goes over every weight.
+const MAX_PASSES: usize = 100;
+
+/// The largest number of steps `tune` moves a weight in one direction in a pass.
+const MAX_STEPS: usize = 100;
+
+/// A position reached in a self-play game, and how the game ended for Red.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrainingPosition {
+    board: Board,
+    result: f64,
+}
+
+impl TrainingPosition {
+    /// Creates a position from a game whose result for Red was `result`, see `result`.
+    pub fn new(board: Board, result: f64) -> Self {
+        Self { board, result }
+    }
+
+    /// Returns the position.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns Red's result in the game: `1.0` for a win, `0.0` for a loss and `0.5`
+    /// for a game that did not end in time.
+    pub fn result(&self) -> f64 {
+        self.result
+    }
+}
+
+/// Plays `games` games of the engine against itself on boards of `dimensions`, and
+/// returns the quiet positions they reached with the result of their game.
+///
+/// Every move is searched within `limits` with a `StandardEvaluator` weighted by
+/// `params`, see `self_play_games`, and the positions are picked with
+/// `training_positions`.
+///
+/// # Panics
+/// Panics if `limits` is infinite, or if `player_count` is not between
+/// `Player::MIN_PLAYERS` and `Player::MAX_PLAYERS`.
+///
+/// # Example
+///
+/// ```
+/// use color_wars::prelude::*;
+///
+/// let positions = self_play(
+///     Dimensions::new(3, 3),
+///     2,
+///     4,
+///     SearchLimits::depth(1),
+///     &EvalParams::default(),
+///     7,
+/// );
+/// let params = tune(&positions, EvalParams::default());
+/// println!("{params}");
+/// ```
+pub fn self_play(
+    dimensions: Dimensions,
+    player_count: usize,
+    games: usize,
+    limits: SearchLimits,
+    params: &EvalParams,
+    seed: u64,
+) -> Vec<TrainingPosition> {
+    let evaluator = StandardEvaluator::new(*params);
+    self_play_games(dimensions, player_count, games, limits, &evaluator, seed)
+        .iter()
+        .flat_map(training_positions)
+        .collect()
+}
+
+/// Plays `games` games of the engine against itself on boards of `dimensions`, with
+/// `player_count` players, and returns them.
+///
+/// Every move is searched within `limits`, scoring positions with `evaluator`. The
+/// starting squares and the first moves of each game are random, drawn from a stream
+/// of its own given by `seed` and the game's index, so the same arguments always play
+/// the same games on a single thread and different seeds play different games. A game
+/// still going after a few moves per square of the board is declared a draw.
+///
+/// # Panics
+/// Panics if `limits` is infinite, or if `player_count` is not between
+/// `Player::MIN_PLAYERS` and `Player::MAX_PLAYERS`.
+pub fn self_play_games(
+    dimensions: Dimensions,
+    player_count: usize,
+    games: usize,
+    limits: SearchLimits,
+    evaluator: &impl Evaluator,
+    seed: u64,
+) -> Vec<Game> {
+    assert!(
+        !limits.is_infinite(),
+        "self-play needs limits to end every search"
+    );
+    assert!(
+        (Player::MIN_PLAYERS..=Player::MAX_PLAYERS).contains(&player_count),
+        "games are played by {} to {} players, not {player_count}",
+        Player::MIN_PLAYERS,
+        Player::MAX_PLAYERS
+    );
+
+    let mut transposition_table = TranspositionTable::default();
+    (0..games)
+        .map(|index| {
+            // Every game draws from its own stream, so games of different runs
+            // differ whatever their seeds and indexes
+            let mut random = Random::new(zobrist::mix(seed) ^ index as u64);
+            // Every player starts on a different random square
+            let mut squares: Vec<_> = dimensions.coordinates().collect();
+            let placements: Vec<_> = (0..player_count)
+                .map(|_| squares.swap_remove(random.below(squares.len())))
+                .collect();
+            let mut game = Game::new(dimensions, &placements);
+
+            while game.result() == GameResult::Ongoing {
+                if game.move_number() == dimensions.area() * GAME_MOVES_PER_SQUARE {
+                    game.declare_draw().expect("the game is ongoing");
+                    break;
+                }
+
+                let board = game.board();
+                let placement = if game.move_number() < OPENING_MOVES {
+                    let moves = board.get_valid_moves();
+                    moves[random.below(moves.len())]
+                } else {
+                    search_with_evaluator(
+                        board,
+                        limits,
+                        &StopHandle::new(),
+                        &mut transposition_table,
+                        evaluator,
+                        |_| {},
+                    )
+                    .best_move()
+                };
+                game.play(placement)
+                    .expect("the engine only plays valid moves");
+            }
+            game
+        })
+        .collect()
+}
+
+/// Returns the quiet positions of `game` after its opening moves, with the result of
+/// the game.
+///
+/// Positions where the player to move can start a chain reaction are left out, their
+/// evaluation says little about the game. So are the positions after the last move.
+pub fn training_positions(game: &Game) -> Vec<TrainingPosition> {
+    let result = match game.result() {
+        GameResult::Won(Player::Red) => 1.0,
+        GameResult::Won(_) => 0.0,
+        GameResult::Ongoing | GameResult::Drawn => 0.5,
+    };
+    game.boards()
+        .iter()
+        .take(game.move_number())
+        .skip(OPENING_MOVES)
+        .filter(|board| {
+            !board
+                .valid_moves()
+                .any(|placement| is_explosive(board, placement))
+        })
+        .map(|board| TrainingPosition {
+            board: board.clone(),
+            result,
+        })
+        .collect()
+}
+
+/// Returns the weights, starting from `params`, whose evaluations of `positions` best
+/// predict the results of their games.
+///
+/// This is the Texel method. An evaluation is turned into Red's expected result with
+/// a logistic curve, whose scale is fitted to `params` first. Each weight is then moved
+/// one step at a time for as long as it lowers the mean squared error of the
+/// expected results, until no weight can. Each pass moves a weight at most
+/// `MAX_STEPS` steps each way, and there are at most `MAX_PASSES` passes, so tuning
+/// evaluates the positions a bounded number of times.
+pub fn tune(positions: &[TrainingPosition], params: EvalParams) -> EvalParams {
+    let scale = fit_scale(positions, &params);
+    let mut weights = params.weights();
+    let mut best_error = mean_squared_error(positions, &params, scale);
+
+    for _ in 0..MAX_PASSES {
+        let mut improved = false;
+        for index in 0..weights.len() {
+            for step in [1, -1] {
+                for _ in 0..MAX_STEPS {
+                    let mut candidate = weights;
+                    candidate[index] += step;
+                    let error =
+                        mean_squared_error(positions, &EvalParams::from_weights(candidate), scale);
+                    if error >= best_error {
+                        break;
+                    }
+                    weights = candidate;
+                    best_error = error;
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+    EvalParams::from_weights(weights)
+}
+
+/// Returns the scale of the logistic curve that makes the evaluations of `params` the
+/// best predictions of the results, among scales growing by a quarter from `1`.
+fn fit_scale(positions: &[TrainingPosition], params: &EvalParams) -> f64 {
+    (0..48)
+        .map(|step| 1.25f64.powi(step))
+        .map(|scale| (scale, mean_squared_error(positions, params, scale)))
+        .reduce(|best, current| if current.1 < best.1 { current } else { best })
+        .map_or(1.0, |(scale, _)| scale)
+}
+
+/// Returns the mean squared difference between the results of `positions` and the
+/// results their evaluations with `params` predict. Positions scored as a win or a
+/// loss are left out, their evaluation does not depend on the weights.
+fn mean_squared_error(positions: &[TrainingPosition], params: &EvalParams, scale: f64) -> f64 {
+    let (total, count) = positions
+        .iter()
+        .filter_map(|position| {
+            let value = evaluate(&position.board, params).value()?;
+            let expected = 1.0 / (1.0 + (-f64::from(value) / scale).exp());
+            Some((position.result - expected).powi(2))
+        })
+        .fold((0.0, 0), |(total, count), error| (total + error, count + 1));
+    if count == 0 {
+        0.0
+    } else {
+        total / count as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::GameRecord;
+
+    fn self_play_positions() -> Vec<TrainingPosition> {
+        self_play(
+            Dimensions::new(3, 4),
+            2,
+            12,
+            SearchLimits::depth(2),
+            &EvalParams::default(),
+            11,
+        )
+    }
+
+    #[test]
+    fn self_play_records_quiet_positions() {
+        let positions = self_play_positions();
+        assert!(!positions.is_empty());
+        assert_eq!(positions, self_play_positions());
+        for position in &positions {
+            let board = position.board();
+            assert!(!board.is_game_over());
+            assert!(
+                !board
+                    .valid_moves()
+                    .any(|placement| is_explosive(board, placement))
+            );
+            assert!([0.0, 0.5, 1.0].contains(&position.result()));
+        }
+    }
+
+    #[test]
+    fn self_play_games_are_finished() {
+        let games = self_play_games(
+            Dimensions::new(3, 3),
+            3,
+            4,
+            SearchLimits::depth(1),
+            &StandardEvaluator::default(),
+            5,
+        );
+        assert_eq!(games.len(), 4);
+        for game in &games {
+            assert_eq!(game.placements().len(), 3);
+            assert_ne!(game.result(), GameResult::Ongoing);
+            assert!(training_positions(game).len() < game.move_number());
+        }
+    }
+
+    #[test]
+    fn seeds_play_different_games() {
+        let records = |seed| -> Vec<String> {
+            self_play_games(
+                Dimensions::new(4, 4),
+                2,
+                2,
+                SearchLimits::depth(1),
+                &StandardEvaluator::default(),
+                seed,
+            )
+            .iter()
+            .map(|game| GameRecord::new(game).to_string())
+            .collect()
+        };
+        let (first, second) = (records(0), records(1));
+        assert_eq!(first, records(0));
+        assert_ne!(first[0], first[1]);
+        assert_ne!(first[0], second[0]);
+        // A later seed is not an earlier one shifted by a game
+        assert_ne!(first[1], second[0]);
+    }
+
+    #[test]
+    fn tuning_lowers_the_error() {
+        let positions = self_play_positions();
+        let params = EvalParams::default();
+        let scale = fit_scale(&positions, &params);
+        let tuned = tune(&positions, params);
+        assert!(
+            mean_squared_error(&positions, &tuned, scale)
+                <= mean_squared_error(&positions, &params, scale)
+        );
+    }
+
+    #[test]
+    fn tuning_follows_the_results() {
+        // Red wins every game where it holds more squares
+        let board = |notation: &str| notation.parse::<Board>().unwrap();
+        let positions = [
+            ("r1r1./b1.. r", 1.0),
+            ("r1../b1b1. r", 0.0),
+            ("r1r1r1/b1.. b", 1.0),
+            ("r1../b1b1b1 b", 0.0),
+        ]
+        .map(|(notation, result)| TrainingPosition {
+            board: board(notation),
+            result,
+        });
+
+        let params = EvalParams::new(1, 0, 0);
+        let tuned = tune(&positions, params);
+        assert!(tuned.square() > params.square());
+        assert!(
+            mean_squared_error(&positions, &tuned, 1.0)
+                < mean_squared_error(&positions, &params, 1.0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "not 5")]
+    fn too_many_players() {
+        self_play_games(
+            Dimensions::new(2, 2),
+            Player::MAX_PLAYERS + 1,
+            1,
+            SearchLimits::depth(1),
+            &StandardEvaluator::default(),
+            0,
+        );
+    }
+
+    #[test]
+    fn no_positions() {
+        assert_eq!(tune(&[], EvalParams::default()), EvalParams::default());
+    }
+}
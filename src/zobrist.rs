@@ -0,0 +1,80 @@
+//! Zobrist keys, the building blocks of `Board::hash_key`.
+//!
+//! A position's key is the XOR of one key per occupied square, one for the player
+//! to move, one for the number of players and one for the size of the board. XOR
+//! undoes itself, so a move only has to flip the keys of the squares it changes.
+//! Boards are sized at runtime, so the keys are derived on demand from a fixed seed
+//! instead of being stored in tables.
+use crate::{dimensions::Dimensions, player::Player, square::Square};
+
+/// Returns the key of `square` at position `index` of the grid, `0` for an empty square.
+pub(crate) fn square_key(index: usize, square: Square) -> u64 {
+    match square.owner() {
+        None => 0,
+        Some(owner) => {
+            let feature =
+                (index as u64) << 16 | (owner.index() as u64) << 8 | square.value() as u64;
+            mix(feature)
+        }
+    }
+}
+
+/// Returns the key of `player` being the player to move.
+pub(crate) fn turn_key(player: Player) -> u64 {
+    mix(u64::MAX - player.index() as u64)
+}
+
+/// Returns the key of a game of `player_count` players.
+pub(crate) fn player_count_key(player_count: usize) -> u64 {
+    mix(u64::MAX - 0x100 - player_count as u64)
+}
+
+/// Returns the key of a board of `dimensions`, so boards of different sizes with
+/// the same squares occupied have different keys.
+pub(crate) fn dimensions_key(dimensions: Dimensions) -> u64 {
+    let feature = (dimensions.rows() as u64) << 32 | dimensions.columns() as u64;
+    mix(u64::MAX - 0x1_0000 - feature)
+}
+
+// Scrambles `feature` into a pseudo-random key with the SplitMix64 finalizer,
+// so every feature gets a distinct, well spread key.
+pub(crate) fn mix(feature: u64) -> u64 {
+    let mut key = feature.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    key = (key ^ (key >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    key = (key ^ (key >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    key ^ (key >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_squares_have_no_key() {
+        assert_eq!(square_key(7, Square::empty()), 0);
+    }
+
+    #[test]
+    fn keys_are_distinct() {
+        let mut keys = Vec::new();
+        for index in 0..64 {
+            for owner in Player::ALL {
+                for value in 1..=4 {
+                    keys.push(square_key(index, Square::occupied(owner, value)));
+                }
+            }
+        }
+        keys.extend(Player::ALL.map(turn_key));
+        keys.extend((Player::MIN_PLAYERS..=Player::MAX_PLAYERS).map(player_count_key));
+        for rows in 2..=8 {
+            for columns in 2..=8 {
+                keys.push(dimensions_key(Dimensions::new(rows, columns)));
+            }
+        }
+
+        let count = keys.len();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), count);
+    }
+}